@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::timer::Event;
+
+const INVERSE: &str = "\u{1b}[7m";
+const RESET: &str = "\u{1b}[0m";
+const BELL: &str = "\u{7}";
+
+/// The `alert` section of the configuration.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Whether phase changes are shown in the pane.
+    enabled: bool,
+    /// For how many seconds the pane flashes.
+    flash: u64,
+    /// Whether to ring the terminal bell.
+    bell: bool,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        AlertConfig {
+            enabled: true,
+            flash: 5,
+            bell: false,
+        }
+    }
+}
+
+/// A banner announcing a phase change, shown until the user acknowledges
+/// it.
+pub struct Alert {
+    event: Event,
+    since: DateTime<Utc>,
+    /// Whether the bell is already rung.
+    rung: bool,
+}
+
+impl Alert {
+    pub fn new(event: Event, now: DateTime<Utc>, config: &AlertConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Alert {
+            event,
+            since: now,
+            rung: !config.bell,
+        })
+    }
+
+    /// The banner, centered in `cols` columns.
+    pub fn banner(&self, cols: usize) -> String {
+        let text = match self.event {
+            Event::WorkEnd => "BREAK TIME",
+            Event::BreakEnd => "WORK TIME",
+            Event::LongBreakStart => "LONG BREAK TIME",
+        };
+        let padding = cols.saturating_sub(text.len());
+        format!("{}{}", " ".repeat(padding / 2), text)
+    }
+
+    /// The terminal bell, the first time only.
+    pub fn bell(&mut self) -> &'static str {
+        if self.rung {
+            ""
+        } else {
+            self.rung = true;
+            BELL
+        }
+    }
+
+    /// Whether the pane is inverted at `now`, it blinks every second for the
+    /// first `flash` seconds.
+    pub fn inverted(&self, now: DateTime<Utc>, config: &AlertConfig) -> bool {
+        let elapsed = (now - self.since).num_seconds();
+        elapsed >= 0 && (elapsed as u64) < config.flash && elapsed % 2 == 0
+    }
+}
+
+/// Render `line` in inverse video, padded to `cols` columns.
+pub fn invert(line: &str, cols: usize) -> String {
+    let len = line.chars().count();
+    format!(
+        "{}{}{}{}",
+        INVERSE,
+        line,
+        " ".repeat(cols.saturating_sub(len)),
+        RESET
+    )
+}
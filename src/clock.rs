@@ -0,0 +1,76 @@
+use pomodoro_clock::template::Fields;
+
+const HEIGHT: usize = 5;
+/// The rows under the digits: a blank one, the phase and the task.
+const CAPTION_HEIGHT: usize = 3;
+/// The blank rows kept around the whole block.
+const MARGIN: usize = 2;
+
+const DIGITS: [[&str; HEIGHT]; 10] = [
+    ["█████", "█   █", "█   █", "█   █", "█████"],
+    ["  █  ", " ██  ", "  █  ", "  █  ", " ███ "],
+    ["█████", "    █", "█████", "█    ", "█████"],
+    ["█████", "    █", "█████", "    █", "█████"],
+    ["█   █", "█   █", "█████", "    █", "    █"],
+    ["█████", "█    ", "█████", "    █", "█████"],
+    ["█████", "█    ", "█████", "█   █", "█████"],
+    ["█████", "    █", "   █ ", "  █  ", "  █  "],
+    ["█████", "█   █", "█████", "█   █", "█████"],
+    ["█████", "█   █", "█████", "    █", "█████"],
+];
+const COLON: [&str; HEIGHT] = [" ", "█", " ", "█", " "];
+
+/// The remaining time in big digits, with the phase and the task below it,
+/// centered in `rows` by `cols`. `None` if the pane is too small for it.
+pub fn lines(fields: &Fields, rows: usize, cols: usize) -> Option<Vec<String>> {
+    let secs = fields.remaining.as_secs();
+    let time = format!("{:02}:{:02}", secs / 60, secs % 60);
+    let glyphs: Vec<&[&str; HEIGHT]> = time
+        .chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) => &DIGITS[d as usize],
+            None => &COLON,
+        })
+        .collect();
+    let width = glyphs
+        .iter()
+        .map(|g| g[0].chars().count() + 1)
+        .sum::<usize>()
+        - 1;
+    if rows < HEIGHT + CAPTION_HEIGHT + MARGIN || cols < width + MARGIN {
+        return None;
+    }
+
+    let mut phase = format!("{} {}/{}", fields.phase, fields.round, fields.rounds);
+    if !fields.profile.is_empty() {
+        phase += &format!(" <{}>", fields.profile);
+    }
+    if fields.paused {
+        phase += " [paused]";
+    }
+    if !fields.pauses.is_empty() {
+        phase += &format!(" ({})", fields.pauses);
+    }
+    let mut block: Vec<String> = (0..HEIGHT)
+        .map(|row| {
+            let row: Vec<&str> = glyphs.iter().map(|g| g[row]).collect();
+            center(&row.join(" "), cols)
+        })
+        .collect();
+    block.push(String::new());
+    block.push(center(&phase, cols));
+    block.push(center(fields.task, cols));
+
+    let mut lines = vec![String::new(); (rows - block.len()) / 2];
+    lines.extend(block);
+    Some(lines)
+}
+
+/// Pad `s` on the left to center it in `cols` columns, it's cut if wider.
+fn center(s: &str, cols: usize) -> String {
+    let len = s.chars().count();
+    if len >= cols {
+        return s.chars().take(cols).collect();
+    }
+    format!("{}{}", " ".repeat((cols - len) / 2), s)
+}
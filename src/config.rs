@@ -0,0 +1,477 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use crate::alert::AlertConfig;
+use crate::notification::Notifications;
+use crate::phase::{self, Phase};
+use crate::template::Formats;
+use crate::theme::Theme;
+use crate::timer::{Kind, Step};
+use crate::timezone::Timezone;
+
+const CONFIG_PATH: &str = "/data/config.json";
+const MAX_MINUTES: u64 = 24 * 60;
+const MAX_ROUNDS: usize = 99;
+
+/// Number of working rounds before the long break, unless configured.
+const DEFAULT_ROUNDS: usize = 4;
+
+/// What to do once the timer has been paused for too long.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseAction {
+    /// Notify the user, again every `minutes` as long as it stays paused.
+    Remind,
+    /// Give up on the current phase, it waits for the user to start it over.
+    Abandon,
+}
+
+/// The `pause_timeout` section of the configuration.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PauseTimeout {
+    /// How long the timer may stay paused, `0` for as long as it takes.
+    minutes: u64,
+    action: PauseAction,
+}
+
+impl Default for PauseTimeout {
+    fn default() -> Self {
+        PauseTimeout {
+            minutes: 0,
+            action: PauseAction::Remind,
+        }
+    }
+}
+
+impl PauseTimeout {
+    /// The longest a pause may last, `None` if it isn't limited.
+    pub fn length(&self) -> Option<chrono::Duration> {
+        match self.minutes {
+            0 => None,
+            minutes => Some(chrono::Duration::minutes(minutes as i64)),
+        }
+    }
+
+    pub fn action(&self) -> PauseAction {
+        self.action
+    }
+}
+
+/// A named set of settings, overriding the top-level ones it sets.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    name: String,
+    working: Option<u64>,
+    resting: Option<u64>,
+    napping: Option<u64>,
+    rounds: Option<usize>,
+    phases: Option<Vec<Phase>>,
+    extend: Option<u64>,
+    notifications: Option<Notifications>,
+}
+
+/// User configuration, read from `/data/config.json`.
+///
+/// Every field is optional, missing ones fall back to the classic
+/// 25/5/15 Pomodoro cycle.
+///
+/// The cycle is either made of `phases`, or of `rounds` times `working`
+/// then `resting`, followed by `napping`.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Length of a working interval, in minutes.
+    working: u64,
+    /// Length of a short break, in minutes.
+    resting: u64,
+    /// Length of the long break, in minutes.
+    napping: u64,
+    /// Number of working rounds before the long break.
+    rounds: usize,
+    /// The phases of the cycle, overriding the classic one.
+    phases: Vec<Phase>,
+    /// The phases actually cycled through.
+    #[serde(skip)]
+    cycle: Vec<Phase>,
+    /// The timing of `cycle`.
+    #[serde(skip)]
+    steps: Vec<Step>,
+    /// Whether a break starts by itself once the working interval ends.
+    auto_start_breaks: bool,
+    /// Whether a working interval starts by itself once the break ends.
+    auto_start_work: bool,
+    /// How much time the extend shortcut adds, in minutes.
+    extend: u64,
+    /// What happens when the timer stays paused for too long.
+    pause_timeout: PauseTimeout,
+    /// Zone of the displayed clock, the host's local zone if unset.
+    timezone: Timezone,
+    /// Templates of the status line.
+    format: Formats,
+    /// How the user is notified of phase changes.
+    notifications: Notifications,
+    /// How phase changes are shown in the pane.
+    alert: AlertConfig,
+    /// Colors of the timer.
+    theme: Theme,
+    /// The profiles to switch to at runtime.
+    profiles: Vec<Profile>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut config = Config {
+            working: 25,
+            resting: 5,
+            napping: 15,
+            rounds: DEFAULT_ROUNDS,
+            phases: Vec::new(),
+            cycle: Vec::new(),
+            steps: Vec::new(),
+            auto_start_breaks: true,
+            auto_start_work: true,
+            extend: 5,
+            pause_timeout: PauseTimeout::default(),
+            timezone: Timezone::default(),
+            format: Formats::default(),
+            notifications: Notifications::default(),
+            alert: AlertConfig::default(),
+            theme: Theme::default(),
+            profiles: Vec::new(),
+        };
+        config.resolve();
+        config
+    }
+}
+
+impl Config {
+    /// Load the configuration file, a missing file is not an error.
+    pub fn load() -> Result<Self, String> {
+        match fs::read_to_string(CONFIG_PATH) {
+            Ok(content) => Config::parse(&content).map_err(|e| format!("{}: {}", CONFIG_PATH, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", CONFIG_PATH, e)),
+        }
+    }
+
+    /// Read a configuration from its JSON `content`.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut config: Config = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        config.resolve();
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn resolve(&mut self) {
+        self.cycle = if self.phases.is_empty() {
+            phase::classic(self.working, self.resting, self.napping, self.rounds)
+        } else {
+            self.phases.clone()
+        };
+        self.steps = self
+            .cycle
+            .iter()
+            .map(|phase| {
+                phase.step(match phase.kind {
+                    Kind::Work => self.auto_start_work,
+                    Kind::Break | Kind::LongBreak => self.auto_start_breaks,
+                })
+            })
+            .collect();
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.validate_settings()?;
+        for (i, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                return Err("a profile has an empty name".to_string());
+            }
+            if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(format!("duplicate profile \"{}\"", profile.name));
+            }
+            self.with_profile(Some(&profile.name))
+                .validate_settings()
+                .map_err(|e| format!("profile \"{}\": {}", profile.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn validate_settings(&self) -> Result<(), String> {
+        for (name, minutes) in &[
+            ("working", self.working),
+            ("resting", self.resting),
+            ("napping", self.napping),
+            ("extend", self.extend),
+        ] {
+            if *minutes == 0 || *minutes > MAX_MINUTES {
+                return Err(format!(
+                    "\"{}\" must be between 1 and {} minutes, got {}",
+                    name, MAX_MINUTES, minutes
+                ));
+            }
+        }
+        if self.pause_timeout.minutes > MAX_MINUTES {
+            return Err(format!(
+                "\"pause_timeout\" must be at most {} minutes, got {}",
+                MAX_MINUTES, self.pause_timeout.minutes
+            ));
+        }
+        if self.rounds == 0 || self.rounds > MAX_ROUNDS {
+            return Err(format!(
+                "\"rounds\" must be between 1 and {}, got {}",
+                MAX_ROUNDS, self.rounds
+            ));
+        }
+        for phase in &self.phases {
+            if phase.name.trim().is_empty() {
+                return Err("a phase has an empty name".to_string());
+            }
+            if phase.minutes == 0 || phase.minutes > MAX_MINUTES {
+                return Err(format!(
+                    "phase \"{}\" must last between 1 and {} minutes, got {}",
+                    phase.name, MAX_MINUTES, phase.minutes
+                ));
+            }
+        }
+        self.notifications.validate()
+    }
+
+    /// The settings of the profile `name`, the top-level ones if it's `None`
+    /// or unknown.
+    pub fn with_profile(&self, name: Option<&str>) -> Config {
+        let mut config = self.clone();
+        let profile = match self.profiles.iter().find(|p| Some(p.name.as_str()) == name) {
+            Some(profile) => profile,
+            None => return config,
+        };
+        config.working = profile.working.unwrap_or(self.working);
+        config.resting = profile.resting.unwrap_or(self.resting);
+        config.napping = profile.napping.unwrap_or(self.napping);
+        config.rounds = profile.rounds.unwrap_or(self.rounds);
+        if let Some(phases) = &profile.phases {
+            config.phases = phases.clone();
+        }
+        config.resolve();
+        config.extend = profile.extend.unwrap_or(self.extend);
+        if let Some(notifications) = &profile.notifications {
+            config.notifications = notifications.clone();
+        }
+        config
+    }
+
+    /// The profile following `name`, cycling back to the top-level settings
+    /// (`None`) after the last one.
+    pub fn next_profile(&self, name: Option<&str>) -> Option<String> {
+        let next = match name {
+            None => 0,
+            Some(name) => self.profiles.iter().position(|p| p.name == name)? + 1,
+        };
+        self.profiles.get(next).map(|p| p.name.clone())
+    }
+
+    pub fn has_profiles(&self) -> bool {
+        !self.profiles.is_empty()
+    }
+
+    /// The phases of the cycle, there is at least one.
+    pub fn cycle(&self) -> &[Phase] {
+        &self.cycle
+    }
+
+    /// The timing of the phases of [`Config::cycle`].
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn extend_interval(&self) -> Duration {
+        Duration::from_secs(self.extend * 60)
+    }
+
+    pub fn pause_timeout(&self) -> &PauseTimeout {
+        &self.pause_timeout
+    }
+
+    pub fn timezone(&self) -> Timezone {
+        self.timezone
+    }
+
+    pub fn format(&self) -> &Formats {
+        &self.format
+    }
+
+    pub fn notifications(&self) -> &Notifications {
+        &self.notifications
+    }
+
+    pub fn alert(&self) -> &AlertConfig {
+        &self.alert
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(config: &Config) -> Vec<u64> {
+        config.cycle().iter().map(|phase| phase.minutes).collect()
+    }
+
+    #[test]
+    fn default_to_the_classic_cycle() {
+        let config = Config::parse("{}").unwrap();
+        assert_eq!(minutes(&config), vec![25, 5, 25, 5, 25, 5, 25, 5, 15]);
+        assert_eq!(config.extend_interval(), Duration::from_secs(5 * 60));
+        assert!(!config.has_profiles());
+    }
+
+    #[test]
+    fn phases_override_the_classic_cycle() {
+        let config = Config::parse(
+            r#"{"working": 50, "phases": [{"name": "Focus", "minutes": 90, "kind": "work"}, {"name": "Walk", "minutes": 20}]}"#,
+        )
+        .unwrap();
+        assert_eq!(minutes(&config), vec![90, 20]);
+        assert_eq!(config.steps()[1].kind, Kind::Break);
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        for (content, error) in &[
+            (
+                r#"{"working": 0}"#,
+                "\"working\" must be between 1 and 1440 minutes, got 0",
+            ),
+            (
+                r#"{"napping": 1441}"#,
+                "\"napping\" must be between 1 and 1440 minutes, got 1441",
+            ),
+            (
+                r#"{"extend": 0}"#,
+                "\"extend\" must be between 1 and 1440 minutes, got 0",
+            ),
+            (
+                r#"{"rounds": 100}"#,
+                "\"rounds\" must be between 1 and 99, got 100",
+            ),
+            (
+                r#"{"pause_timeout": {"minutes": 1441}}"#,
+                "\"pause_timeout\" must be at most 1440 minutes, got 1441",
+            ),
+            (
+                r#"{"phases": [{"name": " ", "minutes": 5}]}"#,
+                "a phase has an empty name",
+            ),
+            (
+                r#"{"phases": [{"name": "Focus", "minutes": 0}]}"#,
+                "phase \"Focus\" must last between 1 and 1440 minutes, got 0",
+            ),
+            (
+                r#"{"notifications": {"command": []}}"#,
+                "notifications: empty command for \"work_end\"",
+            ),
+            (
+                r#"{"notifications": {"break_end": {"command": []}}}"#,
+                "notifications: empty command for \"break_end\"",
+            ),
+        ] {
+            assert_eq!(Config::parse(content).err().as_deref(), Some(*error));
+        }
+    }
+
+    #[test]
+    fn an_empty_command_is_fine_when_turned_off() {
+        for content in &[
+            r#"{"notifications": {"enabled": false, "command": []}}"#,
+            r#"{"notifications": {"command": [], "work_end": {"command": ["true"]}, "break_end": {"enabled": false}, "long_break_start": {"enabled": false}, "pause_reminder": {"enabled": false}, "pause_abandoned": {"enabled": false}}}"#,
+        ] {
+            assert!(Config::parse(content).is_ok(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn reject_unknown_keys() {
+        for content in &[
+            r#"{"workin": 25}"#,
+            r#"{"pause_timeout": {"minute": 5}}"#,
+            r#"{"notifications": {"work_end": {"enable": false}}}"#,
+            r#"{"profiles": [{"name": "deep work", "rests": 10}]}"#,
+        ] {
+            let error = Config::parse(content).err().unwrap();
+            assert!(error.starts_with("unknown field"), "{}: {}", content, error);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_profiles() {
+        for (content, error) in &[
+            (
+                r#"{"profiles": [{"name": ""}]}"#,
+                "a profile has an empty name",
+            ),
+            (
+                r#"{"profiles": [{"name": "focus"}, {"name": "focus"}]}"#,
+                "duplicate profile \"focus\"",
+            ),
+            (
+                r#"{"profiles": [{"name": "focus", "working": 1441}]}"#,
+                "profile \"focus\": \"working\" must be between 1 and 1440 minutes, got 1441",
+            ),
+            (
+                r#"{"profiles": [{"name": "focus", "notifications": {"command": []}}]}"#,
+                "profile \"focus\": notifications: empty command for \"work_end\"",
+            ),
+        ] {
+            assert_eq!(Config::parse(content).err().as_deref(), Some(*error));
+        }
+    }
+
+    #[test]
+    fn profiles_override_the_settings_they_set() {
+        let config = Config::parse(
+            r#"{"resting": 10, "extend": 3, "profiles": [{"name": "deep work", "working": 50, "rounds": 2}, {"name": "quick", "extend": 1}]}"#,
+        )
+        .unwrap();
+        let deep_work = config.with_profile(Some("deep work"));
+        assert_eq!(minutes(&deep_work), vec![50, 10, 50, 10, 15]);
+        assert_eq!(deep_work.extend_interval(), Duration::from_secs(3 * 60));
+        let quick = config.with_profile(Some("quick"));
+        assert_eq!(minutes(&quick), minutes(&config));
+        assert_eq!(quick.extend_interval(), Duration::from_secs(60));
+        for name in &[None, Some("gone")] {
+            assert_eq!(minutes(&config.with_profile(*name)), minutes(&config));
+        }
+    }
+
+    #[test]
+    fn cycle_through_the_profiles() {
+        let config =
+            Config::parse(r#"{"profiles": [{"name": "deep work"}, {"name": "quick"}]}"#).unwrap();
+        assert!(config.has_profiles());
+        let mut profile = None;
+        let mut profiles = Vec::new();
+        for _ in 0..4 {
+            profile = config.next_profile(profile.as_deref());
+            profiles.push(profile.clone());
+        }
+        assert_eq!(
+            profiles,
+            vec![
+                Some("deep work".to_string()),
+                Some("quick".to_string()),
+                None,
+                Some("deep work".to_string()),
+            ]
+        );
+        // A profile gone from the configuration falls back to the top-level
+        // settings.
+        assert_eq!(config.next_profile(Some("gone")), None);
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Error, ErrorKind, Write};
+
+use crate::timer::Pause;
+
+const HISTORY_PATH: &str = "/data/history.jsonl";
+
+/// A finished working interval, one JSON object per line in
+/// `/data/history.jsonl`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Record {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Round of the set, starting from 1.
+    pub round: usize,
+    /// Planned length of the interval, in seconds.
+    pub planned: u64,
+    /// Whether the interval was paused or reset before it ran out.
+    pub interrupted: bool,
+    /// The task label at the end of the interval.
+    #[serde(default)]
+    pub task: Option<String>,
+    /// When the interval was paused and for how long.
+    #[serde(default)]
+    pub pauses: Vec<Pause>,
+}
+
+impl Record {
+    pub fn append(&self) -> io::Result<()> {
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(HISTORY_PATH)?
+            .write_all(&line)
+    }
+}
+
+/// Read all records, a missing history is empty.
+pub fn load() -> io::Result<Vec<Record>> {
+    let content = match fs::read_to_string(HISTORY_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: {}", HISTORY_PATH, i + 1, e),
+                )
+            })
+        })
+        .collect()
+}
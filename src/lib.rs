@@ -0,0 +1,21 @@
+//! The parts of the plugin that don't depend on the Zellij host, so that
+//! they can be tested natively, e.g.
+//! `cargo test --target x86_64-unknown-linux-gnu`.
+
+pub mod alert;
+pub mod config;
+pub mod history;
+pub mod notification;
+pub mod phase;
+pub mod pomo;
+pub mod schema;
+pub mod stats;
+pub mod store;
+pub mod task;
+pub mod template;
+pub mod theme;
+pub mod timer;
+pub mod timezone;
+
+#[cfg(test)]
+mod testing;
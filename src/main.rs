@@ -1,142 +1,160 @@
-use chrono::{Datelike, FixedOffset, Timelike, Utc};
-use serde::{Deserialize, Serialize};
-use std::fmt;
-use std::fs;
-use std::io::{Error, ErrorKind};
-use std::time::Duration;
+use pomodoro_clock::alert::{self, Alert};
+use pomodoro_clock::config::Config;
+use pomodoro_clock::history::{self, Record};
+use pomodoro_clock::pomo::{Outcome, Pomo};
+use pomodoro_clock::stats::Stats;
+use pomodoro_clock::store;
+use pomodoro_clock::timer::{Clock, SystemClock};
 use zellij_tile::prelude::*;
 
-const STATE_SAVING_PATH: &str = "/data/pomo.json";
-const WORKING_INTERVAL: Duration = Duration::from_secs(1500); // 25 min
-const BREAKING_INTERVAL: Duration = Duration::from_secs(300); // 5 min
-const NAPPING_INTERVAL: Duration = Duration::from_secs(900); // 15 min
+mod clock;
+mod progress;
 
-#[derive(Serialize, Deserialize, Clone, Copy)]
-enum Status {
-    Working(usize, Duration),
-    Resting(usize, Duration),
-    Napping(Duration),
+#[derive(Default)]
+struct State {
+    clock: SystemClock,
+    active: bool,
+    /// Whether the saved state has been loaded, it's only read once and
+    /// the state in memory is the reference afterwards.
+    loaded: bool,
+    /// Whether a `set_timeout` is pending, to avoid running several timers.
+    ticking: bool,
+    pomo: Pomo,
+    /// The configuration as loaded.
+    settings: Config,
+    /// The settings of the active profile.
+    config: Config,
+    error: Option<String>,
+    /// Shown instead of the timer when set.
+    stats: Option<Stats>,
+    /// The name of the new task being typed, if any.
+    input: Option<String>,
+    /// The selected entry of the task list.
+    cursor: usize,
+    /// The phase change waiting to be acknowledged.
+    alert: Option<Alert>,
+    /// Whether a reset waits for confirmation.
+    confirming: bool,
+    /// The timer before the last reset, along with the record of the
+    /// interval it abandoned, which is saved once the reset can't be undone.
+    undo: Option<(Pomo, Option<Record>)>,
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Status::Working(0, WORKING_INTERVAL)
+impl State {
+    fn save_records<I: IntoIterator<Item = Record>>(&mut self, records: I) {
+        let mut saved = false;
+        for record in records {
+            if let Err(e) = record.append() {
+                self.error = Some(format!("failed to save history: {}", e));
+                return;
+            }
+            saved = true;
+        }
+        if saved && self.stats.is_some() {
+            self.load_stats();
+        }
     }
-}
 
-impl fmt::Display for Status {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Status::Working(i, d) => write!(
-                f,
-                "Working(round {}): remaining {:02}:{:02}",
-                i + 1,
-                d.as_secs() / 60,
-                d.as_secs() % 60
-            ),
-            Status::Resting(i, d) => write!(
-                f,
-                "Resting(round {}): remaining {:02}:{:02}",
-                i + 1,
-                d.as_secs() / 60,
-                d.as_secs() % 60
-            ),
-            Status::Napping(d) => write!(
-                f,
-                "Napping: remaining {:02}:{:02}",
-                d.as_secs() / 60,
-                d.as_secs() % 60
-            ),
+    fn handle(&mut self, outcome: Outcome) {
+        for command in &outcome.commands {
+            let command: Vec<&str> = command.iter().map(String::as_str).collect();
+            exec_cmd(&command);
+        }
+        self.save_records(outcome.records);
+        if let Some(event) = outcome.event {
+            self.alert = Alert::new(event, self.clock.now(), self.config.alert());
         }
     }
-}
 
-impl Status {
-    fn elapsed(self, d: Duration) -> Self {
-        match self {
-            Status::Working(i, remain) => {
-                if let Some(remain) = remain.checked_sub(d) {
-                    Status::Working(i, remain)
-                } else {
-                    exec_cmd(&vec!["notify-send", "pomodoro", "Time to take a break"]);
-                    Status::Resting(i, BREAKING_INTERVAL)
-                }
-            }
-            Status::Resting(i, remain) => {
-                if let Some(remain) = remain.checked_sub(d) {
-                    Status::Resting(i, remain)
-                } else {
-                    if i + 1 == 4 {
-                        exec_cmd(&vec!["notify-send", "pomodoro", "Time to take some nap"]);
-                        Status::Napping(NAPPING_INTERVAL)
-                    } else {
-                        exec_cmd(&vec!["notify-send", "pomodoro", "Time to start working"]);
-                        Status::Working(i + 1, WORKING_INTERVAL)
-                    }
-                }
-            }
-            Status::Napping(remain) => {
-                if let Some(remain) = remain.checked_sub(d) {
-                    Status::Napping(remain)
-                } else {
-                    exec_cmd(&vec!["notify-send", "pomodoro", "Time to start working"]);
-                    Status::Working(0, WORKING_INTERVAL)
-                }
-            }
+    fn reset(&mut self) {
+        self.forget_undo();
+        let previous = self.pomo.clone();
+        let record = self.pomo.reset(&self.config, &self.clock);
+        self.undo = Some((previous, record));
+    }
+
+    /// Bring back the timer as it was before the last reset, the task list
+    /// is left as is.
+    fn undo(&mut self) {
+        if let Some((pomo, _)) = self.undo.take() {
+            let tasks = std::mem::take(&mut self.pomo.tasks);
+            self.pomo = pomo;
+            self.pomo.tasks = tasks;
+            self.apply_profile();
         }
     }
-}
 
-#[derive(Serialize, Deserialize, Default)]
-struct Pomo {
-    paused: bool,
-    status: Status,
-}
+    /// Switch to the profile following the active one, the running phase
+    /// goes on as it is and the profile applies from the next one.
+    fn next_profile(&mut self) {
+        let profile = self.settings.next_profile(self.pomo.profile());
+        self.pomo.set_profile(profile);
+        self.apply_profile();
+    }
 
-impl Pomo {
-    fn new() -> Self {
-        Pomo::default()
+    fn apply_profile(&mut self) {
+        self.config = self.settings.with_profile(self.pomo.profile());
     }
 
-    fn elapsed(&mut self, dur: Duration) {
-        if self.paused {
-            return;
+    fn forget_undo(&mut self) {
+        if let Some((_, record)) = self.undo.take() {
+            self.save_records(record);
         }
-        self.status = self.status.elapsed(dur);
     }
 
-    fn toggle_pause(&mut self) {
-        self.paused ^= true;
+    fn tips(&self) -> String {
+        let mut tips = self.pomo.shortcuts(&self.config);
+        if self.undo.is_some() && !self.pomo.waiting() {
+            tips += ", <u> => undo reset";
+        }
+        tips
     }
 
-    fn shortcuts(&self) -> String {
-        format!(
-            "Tip: <space> => {pause_or_resume}, <r> => reset",
-            pause_or_resume = if self.paused { "resume" } else { "pause" }
-        )
+    fn new_task(&mut self, key: Key) {
+        let input = self.input.get_or_insert_with(String::new);
+        match key {
+            Key::Char('\n') => {
+                let name = input.trim().to_string();
+                if !name.is_empty() {
+                    self.pomo.tasks.add(name);
+                    self.cursor = self.pomo.tasks.len() - 1;
+                }
+                self.input = None;
+            }
+            Key::Char(c) if !c.is_control() => input.push(c),
+            Key::Backspace => {
+                input.pop();
+            }
+            Key::Esc => self.input = None,
+            _ => (),
+        }
     }
-}
 
-impl fmt::Display for Pomo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}{}",
-            self.status,
-            if self.paused { " [paused]" } else { "" },
-        )
+    fn load_stats(&mut self) {
+        match history::load() {
+            Ok(records) => {
+                self.stats = Some(Stats::new(
+                    &records,
+                    self.config.timezone(),
+                    self.clock.now(),
+                ))
+            }
+            Err(e) => self.error = Some(format!("failed to load history: {}", e)),
+        }
     }
 }
 
-#[derive(Default)]
-struct State {
-    active: bool,
-    pomo: Pomo,
-}
-
 register_plugin!(State);
 impl ZellijPlugin for State {
     fn load(&mut self) {
+        match Config::load() {
+            Ok(config) => {
+                self.config = config.clone();
+                self.settings = config;
+            }
+            Err(e) => self.error = Some(e),
+        }
+
         subscribe(&[
             EventType::Key,
             EventType::Timer,
@@ -147,52 +165,180 @@ impl ZellijPlugin for State {
 
     fn update(&mut self, event: Event) {
         match event {
+            // What the user is typing or answering comes before the alert.
+            Event::Key(key) if self.input.is_some() => self.new_task(key),
+            Event::Key(Key::Char('y')) | Event::Mouse(Mouse::RightClick(_, _))
+                if self.confirming =>
+            {
+                self.confirming = false;
+                self.reset();
+            }
+            Event::Key(_) | Event::Mouse(Mouse::LeftClick(_, _)) if self.confirming => {
+                self.confirming = false
+            }
+            // The other shortcuts keep working while the alert is shown or
+            // the timer waits, and the statistics don't show either of them.
+            Event::Key(Key::Char(' ')) | Event::Mouse(Mouse::LeftClick(_, _))
+                if self.stats.is_none() && (self.alert.is_some() || self.pomo.waiting()) =>
+            {
+                self.alert = None;
+                self.pomo.start(&self.config, &self.clock);
+            }
+            Event::Key(Key::Char('t')) => self.input = Some(String::new()),
+            Event::Key(Key::Up) => self.cursor = self.cursor.saturating_sub(1),
+            Event::Key(Key::Down) if self.cursor + 1 < self.pomo.tasks.len() => self.cursor += 1,
+            Event::Key(Key::Char('\n')) => self.pomo.tasks.toggle_active(self.cursor),
+            Event::Key(Key::Char('+')) => self.pomo.tasks.adjust_estimate(self.cursor, true),
+            Event::Key(Key::Char('-')) => self.pomo.tasks.adjust_estimate(self.cursor, false),
+            Event::Key(Key::Char('d')) | Event::Key(Key::Delete) => {
+                self.pomo.tasks.remove(self.cursor);
+                self.cursor = self.cursor.min(self.pomo.tasks.len().saturating_sub(1));
+            }
             Event::Key(Key::Char('r')) | Event::Mouse(Mouse::RightClick(_, _)) => {
-                self.pomo = Pomo::new()
+                self.confirming = true
+            }
+            Event::Key(Key::Char('u')) => self.undo(),
+            Event::Key(Key::Char('n')) => {
+                let outcome = self.pomo.skip(&self.config, &self.clock);
+                self.handle(outcome);
             }
+            Event::Key(Key::Char('e')) => self.pomo.extend(&self.config),
+            Event::Key(Key::Char('p')) if self.settings.has_profiles() => self.next_profile(),
             Event::Key(Key::Char(' ')) | Event::Mouse(Mouse::LeftClick(_, _)) => {
-                self.pomo.toggle_pause()
+                self.pomo.toggle_pause(&self.config, &self.clock)
             }
-            Event::Timer(t) => {
-                if self.active {
-                    self.pomo.elapsed(Duration::from_secs_f64(t));
-                    set_timeout(1.0);
-                }
+            Event::Key(Key::Char('s')) if self.stats.is_none() => self.load_stats(),
+            Event::Key(Key::Char('s')) => self.stats = None,
+            Event::Key(Key::Esc) => {
+                self.stats = None;
+                self.error = None;
             }
+            Event::Timer(_) if self.active => {
+                let outcome = self.pomo.tick(&self.config, &self.clock);
+                self.handle(outcome);
+                set_timeout(1.0);
+            }
+            Event::Timer(_) => self.ticking = false,
             Event::Visible(true) => {
                 self.active = true;
-                set_timeout(1.0);
+                if !self.ticking {
+                    self.ticking = true;
+                    set_timeout(1.0);
+                }
 
-                match fs::File::open(STATE_SAVING_PATH).and_then(|f| {
-                    serde_json::from_reader(f).map_err(|e| Error::new(ErrorKind::Other, e))
-                }) {
-                    Ok(pomo) => self.pomo = pomo,
-                    Err(_) => self.pomo = Pomo::new(),
+                if !self.loaded {
+                    self.loaded = true;
+                    let (pomo, error) = store::load();
+                    self.pomo = pomo.unwrap_or_else(|| Pomo::new(&self.settings, &self.clock));
+                    self.apply_profile();
+                    if error.is_some() {
+                        self.error = error;
+                    }
                 }
+                // Replay what happened while we were hidden.
+                let outcome = self.pomo.tick(&self.config, &self.clock);
+                self.handle(outcome);
             }
             Event::Visible(false) => {
                 self.active = false;
-                fs::write(STATE_SAVING_PATH, serde_json::to_vec(&self.pomo).unwrap()).unwrap();
+                self.forget_undo();
+                if let Err(e) = store::save(&self.pomo) {
+                    self.error = Some(format!("failed to save state: {}", e));
+                }
             }
             _ => (),
         }
     }
 
-    fn render(&mut self, rows: usize, _cols: usize) {
-        let china_timezone = FixedOffset::east(8 * 3600);
-        let now = Utc::now().with_timezone(&china_timezone);
-        println!(
-            "{pomo} | {hour:02}:{minute:02} {year}-{month:02}-{day:02} {weekday}",
-            pomo = self.pomo,
-            hour = now.hour(),
-            minute = now.minute(),
-            year = now.year(),
-            month = now.month(),
-            day = now.day(),
-            weekday = now.weekday(),
-        );
-        if rows > 1 {
-            println!("{}", self.pomo.shortcuts());
+    fn render(&mut self, rows: usize, cols: usize) {
+        let notice = if let Some(input) = &self.input {
+            Some(format!(
+                "New task: {}_ (<enter> => add, <esc> => cancel)",
+                input
+            ))
+        } else if self.confirming {
+            Some(
+                "Reset the timer? (<y> or right-click => reset, any other key => cancel)"
+                    .to_string(),
+            )
+        } else {
+            self.error
+                .as_ref()
+                .map(|error| format!("Error: {} (<esc> => dismiss)", error))
+        };
+        if let (Some(notice), 1) = (&notice, rows) {
+            println!("{}", notice);
+            return;
+        }
+
+        if let Some(stats) = &self.stats {
+            for line in stats.lines(rows, cols) {
+                println!("{}", line);
+            }
+            return;
+        }
+
+        let now = self.clock.now();
+        let fields = self.pomo.fields(&self.config, &self.clock);
+        let held = self.pomo.paused() || self.pomo.waiting();
+        let theme = self.config.theme();
+        let current = self.pomo.current(&self.config);
+        let mut lines = Vec::new();
+        if self.alert.is_none() {
+            // Tall panes get a big countdown on top, as long as it leaves room
+            // for the status line, the progress bar, the tips and the tasks.
+            let room = rows.saturating_sub(3 + self.pomo.tasks.len());
+            if let Some(clock) = clock::lines(&fields, room, cols) {
+                lines.extend(clock.iter().map(|line| theme.paint(line, current, held)));
+            }
+        }
+        let top = lines.len();
+        match &mut self.alert {
+            Some(alert) => {
+                print!("{}", alert.bell());
+                lines.push(alert.banner(cols));
+            }
+            None => {
+                let line = self.config.format().render(&fields, cols);
+                lines.push(theme.paint(&line, current, held));
+                if rows - top > 2 {
+                    let line = progress::line(
+                        self.pomo.completed(&self.config),
+                        fields.rounds,
+                        fields.progress,
+                        cols,
+                    );
+                    lines.push(theme.paint(&line, current, held));
+                }
+            }
+        }
+        // A single row only has room for the status line or the banner.
+        if rows - top > 1 {
+            if let Some(notice) = notice {
+                lines.push(notice);
+            } else if self.alert.is_some() && !self.pomo.waiting() {
+                lines.push("Press <space> or click to dismiss".to_string());
+            } else {
+                lines.push(self.tips());
+            }
+        }
+        if rows > lines.len() {
+            let rows = rows - lines.len();
+            lines.extend(self.pomo.tasks.lines(self.cursor, rows, cols));
+        }
+
+        match &self.alert {
+            Some(alert) if alert.inverted(now, self.config.alert()) => {
+                lines.resize(rows, String::new());
+                for line in lines {
+                    println!("{}", alert::invert(&line, cols));
+                }
+            }
+            _ => {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
         }
     }
 }
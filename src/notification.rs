@@ -0,0 +1,259 @@
+use serde::Deserialize;
+
+use crate::template::{Fields, Template};
+use crate::timer::Event;
+
+fn default_message(event: Event) -> &'static str {
+    match event {
+        Event::WorkEnd => "Time to take a break",
+        Event::BreakEnd => "Time to start working",
+        Event::LongBreakStart => "Time to take some nap",
+    }
+}
+
+/// How to notify the user of one kind of [`Event`].
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notification {
+    enabled: bool,
+    /// Overrides the command shared by all the notifications.
+    command: Option<Vec<String>>,
+    /// Overrides the default message.
+    message: Option<Template>,
+}
+
+impl Default for Notification {
+    fn default() -> Self {
+        Notification {
+            enabled: true,
+            command: None,
+            message: None,
+        }
+    }
+}
+
+/// The `notifications` section of the configuration.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notifications {
+    /// Turns all the notifications off when false.
+    enabled: bool,
+    /// The command to run, `{message}` in its arguments is replaced by the
+    /// message.
+    command: Vec<String>,
+    work_end: Notification,
+    break_end: Notification,
+    long_break_start: Notification,
+    /// Sent when the timer has been paused for too long.
+    pause_reminder: Notification,
+    /// Sent when the current phase is abandoned after a too long pause.
+    pause_abandoned: Notification,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications {
+            enabled: true,
+            command: vec![
+                "notify-send".to_string(),
+                "pomodoro".to_string(),
+                "{message}".to_string(),
+            ],
+            work_end: Notification::default(),
+            break_end: Notification::default(),
+            long_break_start: Notification::default(),
+            pause_reminder: Notification::default(),
+            pause_abandoned: Notification::default(),
+        }
+    }
+}
+
+impl Notifications {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, notification) in &[
+            ("work_end", &self.work_end),
+            ("break_end", &self.break_end),
+            ("long_break_start", &self.long_break_start),
+            ("pause_reminder", &self.pause_reminder),
+            ("pause_abandoned", &self.pause_abandoned),
+        ] {
+            let command = notification.command.as_ref().unwrap_or(&self.command);
+            if self.enabled && notification.enabled && command.is_empty() {
+                return Err(format!("notifications: empty command for \"{}\"", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The command notifying `event`, `None` if it's turned off. Its message
+    /// (`message` if set) is rendered with `fields`.
+    pub fn command(
+        &self,
+        event: Event,
+        message: Option<&Template>,
+        fields: &Fields,
+    ) -> Option<Vec<String>> {
+        let notification = match event {
+            Event::WorkEnd => &self.work_end,
+            Event::BreakEnd => &self.break_end,
+            Event::LongBreakStart => &self.long_break_start,
+        };
+        self.build(
+            notification,
+            message.or(notification.message.as_ref()),
+            default_message(event),
+            fields,
+        )
+    }
+
+    /// The command reminding the user that the timer is still paused.
+    pub fn reminder(&self, fields: &Fields) -> Option<Vec<String>> {
+        self.build(
+            &self.pause_reminder,
+            self.pause_reminder.message.as_ref(),
+            "The timer is still paused",
+            fields,
+        )
+    }
+
+    /// The command telling the user that the current phase is abandoned.
+    pub fn abandoned(&self, fields: &Fields) -> Option<Vec<String>> {
+        self.build(
+            &self.pause_abandoned,
+            self.pause_abandoned.message.as_ref(),
+            "Paused for too long, the phase starts over",
+            fields,
+        )
+    }
+
+    fn build(
+        &self,
+        notification: &Notification,
+        message: Option<&Template>,
+        default: &str,
+        fields: &Fields,
+    ) -> Option<Vec<String>> {
+        if !self.enabled || !notification.enabled {
+            return None;
+        }
+        let message = match message {
+            Some(message) => message.render(fields),
+            None => default.to_string(),
+        };
+        let command = notification
+            .command
+            .as_ref()
+            .unwrap_or(&self.command)
+            .iter()
+            .map(|arg| arg.replace("{message}", &message))
+            .collect();
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn fields() -> Fields<'static> {
+        Fields {
+            status: "Resting(round 1/4): remaining 05:00".to_string(),
+            phase: "Resting",
+            icon: '○',
+            remaining: Duration::from_secs(5 * 60),
+            round: 1,
+            rounds: 4,
+            paused: false,
+            progress: 0,
+            now: "2022-06-21T10:30:00+02:00".parse().unwrap(),
+            task: "write docs",
+            profile: "",
+            pauses: String::new(),
+        }
+    }
+
+    fn notifications(value: serde_json::Value) -> Notifications {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn substitute_the_message() {
+        let notifications =
+            notifications(json!({"command": ["say", "-m", "{message}!", "x{message}x"]}));
+        assert_eq!(
+            notifications.command(Event::WorkEnd, None, &fields()),
+            Some(vec![
+                "say".to_string(),
+                "-m".to_string(),
+                "Time to take a break!".to_string(),
+                "xTime to take a breakx".to_string(),
+            ])
+        );
+        assert_eq!(
+            Notifications::default().reminder(&fields()),
+            Some(vec![
+                "notify-send".to_string(),
+                "pomodoro".to_string(),
+                "The timer is still paused".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn override_the_command_of_an_event() {
+        let notifications = notifications(json!({
+            "command": ["notify-send", "{message}"],
+            "long_break_start": {"command": ["play", "gong.wav"]},
+        }));
+        let fields = fields();
+        assert_eq!(
+            notifications.command(Event::LongBreakStart, None, &fields),
+            Some(vec!["play".to_string(), "gong.wav".to_string()])
+        );
+        assert_eq!(
+            notifications.command(Event::BreakEnd, None, &fields),
+            Some(vec![
+                "notify-send".to_string(),
+                "Time to start working".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn the_message_of_the_phase_comes_first() {
+        let notifications = notifications(json!({
+            "command": ["{message}"],
+            "work_end": {"message": "Break, {task} can wait"},
+        }));
+        let fields = fields();
+        assert_eq!(
+            notifications.command(Event::WorkEnd, None, &fields),
+            Some(vec!["Break, write docs can wait".to_string()])
+        );
+        let phase = Template::parse("{phase} for {remaining}").unwrap();
+        assert_eq!(
+            notifications.command(Event::WorkEnd, Some(&phase), &fields),
+            Some(vec!["Resting for 05:00".to_string()])
+        );
+    }
+
+    #[test]
+    fn turn_notifications_off() {
+        let fields = fields();
+        let all_off = notifications(json!({"enabled": false}));
+        assert_eq!(all_off.command(Event::WorkEnd, None, &fields), None);
+        assert_eq!(all_off.reminder(&fields), None);
+        assert_eq!(all_off.abandoned(&fields), None);
+
+        let some_off = notifications(json!({
+            "break_end": {"enabled": false},
+            "pause_abandoned": {"enabled": false},
+        }));
+        assert_eq!(some_off.command(Event::BreakEnd, None, &fields), None);
+        assert_eq!(some_off.abandoned(&fields), None);
+        assert!(some_off.command(Event::WorkEnd, None, &fields).is_some());
+        assert!(some_off.reminder(&fields).is_some());
+    }
+}
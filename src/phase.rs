@@ -0,0 +1,64 @@
+use chrono::Duration;
+use serde::Deserialize;
+
+use crate::template::Template;
+use crate::theme::Color;
+use crate::timer::{Kind, Step};
+
+/// The symbol of a phase of `kind`.
+pub fn icon(kind: Kind) -> char {
+    match kind {
+        Kind::Work => '●',
+        Kind::Break => '○',
+        Kind::LongBreak => '◎',
+    }
+}
+
+/// A phase of the cycle, e.g. `{"name": "Stretch", "minutes": 3}`.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    pub name: String,
+    pub minutes: u64,
+    #[serde(default)]
+    pub kind: Kind,
+    /// Overrides the message of the notification sent when it starts.
+    #[serde(default)]
+    pub message: Option<Template>,
+    /// Overrides the color the theme gives to its kind.
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+impl Phase {
+    pub fn new(name: &str, minutes: u64, kind: Kind) -> Self {
+        Phase {
+            name: name.to_string(),
+            minutes,
+            kind,
+            message: None,
+            color: None,
+        }
+    }
+
+    /// The phase as far as the timer is concerned, `auto_start` tells
+    /// whether it starts by itself.
+    pub fn step(&self, auto_start: bool) -> Step {
+        Step {
+            kind: self.kind,
+            length: Duration::minutes(self.minutes as i64),
+            auto_start,
+        }
+    }
+}
+
+/// The classic cycle: `rounds` times working then resting, then napping.
+pub fn classic(working: u64, resting: u64, napping: u64, rounds: usize) -> Vec<Phase> {
+    let mut phases = Vec::with_capacity(rounds * 2 + 1);
+    for _ in 0..rounds {
+        phases.push(Phase::new("Working", working, Kind::Work));
+        phases.push(Phase::new("Resting", resting, Kind::Break));
+    }
+    phases.push(Phase::new("Napping", napping, Kind::LongBreak));
+    phases
+}
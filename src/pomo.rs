@@ -0,0 +1,405 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, PauseAction};
+use crate::history::Record;
+use crate::phase::{self, Phase};
+use crate::task::Tasks;
+use crate::template::Fields;
+use crate::timer::{self, Clock, Event, Interval, Kind, Pause, SystemClock, Timer};
+
+/// What happened while advancing the timer.
+#[derive(Default)]
+pub struct Outcome {
+    /// Records of the finished working intervals.
+    pub records: Vec<Record>,
+    /// The phase change to tell the user about, if any.
+    pub event: Option<Event>,
+    /// The notification commands to run on the host.
+    pub commands: Vec<Vec<String>>,
+}
+
+/// The timer along with what it's used for.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Pomo {
+    #[serde(flatten)]
+    timer: Timer,
+    /// What the user is working on.
+    #[serde(default)]
+    pub tasks: Tasks,
+    /// The name of the active profile, the top-level settings if `None`.
+    #[serde(default)]
+    profile: Option<String>,
+    /// When the user was last reminded that the timer is paused.
+    #[serde(default)]
+    reminded: Option<DateTime<Utc>>,
+}
+
+impl Default for Pomo {
+    fn default() -> Self {
+        Pomo::new(&Config::default(), &SystemClock)
+    }
+}
+
+impl Pomo {
+    pub fn new(config: &Config, clock: &impl Clock) -> Self {
+        Pomo {
+            timer: Timer::new(config.steps(), clock),
+            tasks: Tasks::default(),
+            profile: None,
+            reminded: None,
+        }
+    }
+
+    /// Check a restored timer, see [`Timer::validate`].
+    pub fn validate(&self) -> Result<(), String> {
+        self.timer.validate()
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Switch to the profile `name`, it applies from the next phase on.
+    pub fn set_profile(&mut self, name: Option<String>) {
+        self.profile = name;
+    }
+
+    pub fn current<'a>(&self, config: &'a Config) -> &'a Phase {
+        &config.cycle()[self.timer.index(config.steps())]
+    }
+
+    /// The working phases of the cycle before the current one.
+    pub fn completed(&self, config: &Config) -> usize {
+        self.timer.completed(config.steps())
+    }
+
+    pub fn paused(&self) -> bool {
+        self.timer.paused()
+    }
+
+    pub fn waiting(&self) -> bool {
+        self.timer.waiting()
+    }
+
+    /// Catch up with `clock`, replaying every phase that ended in the
+    /// meantime.
+    pub fn tick(&mut self, config: &Config, clock: &impl Clock) -> Outcome {
+        let reminder = self.check_pause(config, clock);
+        let outcome = self.timer.tick(config.steps(), clock);
+        let mut outcome = self.settle(outcome, config, clock);
+        outcome.records.extend(reminder.records);
+        outcome.commands.extend(reminder.commands);
+        outcome
+    }
+
+    /// Act on a pause lasting longer than the `pause_timeout`.
+    fn check_pause(&mut self, config: &Config, clock: &impl Clock) -> Outcome {
+        let mut outcome = Outcome::default();
+        let timeout = config.pause_timeout();
+        let (length, since) = match (timeout.length(), self.timer.paused_since()) {
+            (Some(length), Some(since)) => (length, since),
+            _ => return outcome,
+        };
+        match timeout.action() {
+            PauseAction::Remind => {
+                let last = self.reminded.filter(|at| *at > since).unwrap_or(since);
+                if clock.now() - last >= length {
+                    self.reminded = Some(clock.now());
+                    outcome
+                        .commands
+                        .extend(config.notifications().reminder(&self.fields(config, clock)));
+                }
+            }
+            PauseAction::Abandon if clock.now() - since >= length => {
+                let interval = self.timer.abandon(config.steps(), since + length);
+                outcome
+                    .records
+                    .extend(interval.map(|interval| self.record(interval)));
+                outcome.commands.extend(
+                    config
+                        .notifications()
+                        .abandoned(&self.fields(config, clock)),
+                );
+            }
+            PauseAction::Abandon => (),
+        }
+        outcome
+    }
+
+    /// Finish the current phase right away, the next one starts at once.
+    pub fn skip(&mut self, config: &Config, clock: &impl Clock) -> Outcome {
+        let outcome = self.timer.skip(config.steps(), clock);
+        self.settle(outcome, config, clock)
+    }
+
+    /// Record the finished working intervals and credit the completed ones,
+    /// and notify the phase change.
+    fn settle(&mut self, outcome: timer::Outcome, config: &Config, clock: &impl Clock) -> Outcome {
+        let mut records = Vec::new();
+        for interval in outcome.intervals {
+            // Just like the stats, only count the pomodoros seen through.
+            if !interval.interrupted {
+                self.tasks.credit();
+            }
+            records.push(self.record(interval));
+        }
+        let mut commands = Vec::new();
+        if let Some(event) = outcome.event {
+            let phase = self.current(config);
+            commands.extend(config.notifications().command(
+                event,
+                phase.message.as_ref(),
+                &self.fields(config, clock),
+            ));
+        }
+        Outcome {
+            records,
+            event: outcome.event,
+            commands,
+        }
+    }
+
+    pub fn extend(&mut self, config: &Config) {
+        self.timer.extend(
+            config.steps(),
+            Duration::from_std(config.extend_interval()).unwrap(),
+        );
+    }
+
+    /// Start over, returns the record of an abandoned working interval.
+    pub fn reset(&mut self, config: &Config, clock: &impl Clock) -> Option<Record> {
+        self.timer
+            .reset(config.steps(), clock)
+            .map(|interval| self.record(interval))
+    }
+
+    fn record(&self, interval: Interval) -> Record {
+        Record {
+            start: interval.start,
+            end: interval.end,
+            round: interval.round,
+            planned: interval.planned,
+            interrupted: interval.interrupted,
+            task: self.tasks.active().map(|task| task.name.clone()),
+            pauses: interval.pauses,
+        }
+    }
+
+    /// Start the phase waiting for the user.
+    pub fn start(&mut self, config: &Config, clock: &impl Clock) {
+        self.timer.start(config.steps(), clock)
+    }
+
+    pub fn toggle_pause(&mut self, config: &Config, clock: &impl Clock) {
+        self.timer.toggle_pause(config.steps(), clock)
+    }
+
+    /// The full status, e.g. `Working(round 1/4): remaining 24:59`.
+    pub fn describe(&self, config: &Config, clock: &impl Clock) -> String {
+        let remaining = self.timer.remaining(clock).as_secs();
+        let mut s = String::new();
+        if let Some(profile) = &self.profile {
+            s += &format!("<{}> ", profile);
+        }
+        if let Some(task) = self.tasks.active() {
+            s += &format!("[{}] ", task);
+        }
+        let current = self.current(config);
+        let phase = match (current.kind, self.timer.rounds(config.steps())) {
+            (Kind::LongBreak, _) | (_, (_, 0)) => current.name.clone(),
+            (_, (round, rounds)) => format!("{}(round {}/{})", current.name, round, rounds),
+        };
+        if self.waiting() {
+            return s + "Waiting to start " + &phase;
+        }
+        s += &format!(
+            "{}: remaining {:02}:{:02}",
+            phase,
+            remaining / 60,
+            remaining % 60
+        );
+        if self.paused() {
+            s += " [paused]";
+        }
+        let pauses = summarize(&self.timer.pauses(config.steps(), clock));
+        if !pauses.is_empty() {
+            s += &format!(" ({})", pauses);
+        }
+        s
+    }
+
+    pub fn fields<'a>(&'a self, config: &'a Config, clock: &impl Clock) -> Fields<'a> {
+        let current = self.current(config);
+        let (round, rounds) = self.timer.rounds(config.steps());
+        Fields {
+            status: self.describe(config, clock),
+            phase: &current.name,
+            icon: phase::icon(current.kind),
+            remaining: self.timer.remaining(clock),
+            round,
+            rounds,
+            paused: self.paused(),
+            progress: self.timer.progress(config.steps(), clock),
+            now: config.timezone().localize(clock.now()),
+            task: self.tasks.active().map_or("", |task| &task.name),
+            profile: self.profile().unwrap_or(""),
+            pauses: summarize(&self.timer.pauses(config.steps(), clock)),
+        }
+    }
+
+    pub fn shortcuts(&self, config: &Config) -> String {
+        if self.waiting() {
+            return format!(
+                "Tip: press <space> or click to start {}",
+                self.current(config).name
+            );
+        }
+        let mut tips = format!(
+            "Tip: <space> => {pause_or_resume}, <r> => reset, <n> => next, <e> => +{extend}m, <t> => new task, <s> => stats",
+            pause_or_resume = if self.paused() { "resume" } else { "pause" },
+            extend = config.extend_interval().as_secs() / 60,
+        );
+        if config.has_profiles() {
+            tips += ", <p> => profile";
+        }
+        tips
+    }
+}
+
+/// How often and how long the timer was paused, e.g.
+/// `2 interruptions, 3m paused`, nothing if it wasn't.
+fn summarize(pauses: &[Pause]) -> String {
+    if pauses.is_empty() {
+        return String::new();
+    }
+    let seconds: i64 = pauses.iter().map(|pause| pause.seconds).sum();
+    format!(
+        "{} interruption{}, {} paused",
+        pauses.len(),
+        if pauses.len() == 1 { "" } else { "s" },
+        if seconds < 60 {
+            format!("{}s", seconds)
+        } else {
+            format!("{}m", seconds / 60)
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ManualClock;
+
+    fn settings() -> Config {
+        Config::parse(
+            r#"{"profiles": [{"name": "long", "working": 50, "resting": 10, "rounds": 2}]}"#,
+        )
+        .unwrap()
+    }
+
+    fn remaining_minutes(pomo: &Pomo, config: &Config, clock: &ManualClock) -> u64 {
+        pomo.fields(config, clock).remaining.as_secs() / 60
+    }
+
+    fn with_pause_timeout(action: &str) -> Config {
+        Config::parse(&format!(
+            r#"{{"pause_timeout": {{"minutes": 10, "action": "{}"}}}}"#,
+            action
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn abandon_once_the_pause_timeout_is_over() {
+        let (config, clock) = (with_pause_timeout("abandon"), ManualClock::new());
+        let mut pomo = Pomo::new(&config, &clock);
+        clock.advance(5);
+        pomo.toggle_pause(&config, &clock);
+        clock.advance(9);
+        assert!(pomo.tick(&config, &clock).records.is_empty());
+        assert!(pomo.paused());
+
+        clock.advance(1);
+        let outcome = pomo.tick(&config, &clock);
+        assert_eq!(outcome.commands.len(), 1);
+        let record = &outcome.records[0];
+        assert_eq!((record.start, record.end), (clock.at(0), clock.at(15)));
+        assert!(record.interrupted);
+        assert_eq!(record.pauses[0].seconds, 10 * 60);
+        assert!(pomo.waiting());
+        assert_eq!(remaining_minutes(&pomo, &config, &clock), 25);
+    }
+
+    #[test]
+    fn remind_every_pause_timeout() {
+        let (config, clock) = (with_pause_timeout("remind"), ManualClock::new());
+        let mut pomo = Pomo::new(&config, &clock);
+        pomo.toggle_pause(&config, &clock);
+        let mut reminders = 0;
+        for _ in 0..25 {
+            clock.advance(1);
+            reminders += pomo.tick(&config, &clock).commands.len();
+        }
+        assert_eq!(reminders, 2);
+        assert!(pomo.paused());
+    }
+
+    #[test]
+    fn credit_completed_intervals_only() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let mut pomo = Pomo::new(&config, &clock);
+        pomo.tasks.add("write docs".to_string());
+        clock.advance(25);
+        pomo.tick(&config, &clock);
+        clock.advance(5);
+        pomo.tick(&config, &clock);
+        assert_eq!(pomo.tasks.active().unwrap().completed, 1);
+
+        clock.advance(10);
+        let outcome = pomo.skip(&config, &clock);
+        assert!(outcome.records[0].interrupted);
+        assert_eq!(outcome.records[0].task.as_deref(), Some("write docs"));
+        assert_eq!(pomo.tasks.active().unwrap().completed, 1);
+    }
+
+    #[test]
+    fn switch_profile_during_a_break() {
+        let (settings, clock) = (settings(), ManualClock::new());
+        let mut pomo = Pomo::new(&settings, &clock);
+        clock.advance(26);
+        pomo.tick(&settings, &clock);
+
+        pomo.set_profile(Some("long".to_string()));
+        let config = settings.with_profile(pomo.profile());
+        assert_eq!(pomo.current(&config).name, "Resting");
+        assert_eq!(remaining_minutes(&pomo, &config, &clock), 4);
+
+        clock.advance(4);
+        let outcome = pomo.tick(&config, &clock);
+        assert!(outcome.records.is_empty());
+        assert_eq!(pomo.current(&config).name, "Working");
+        assert_eq!(remaining_minutes(&pomo, &config, &clock), 50);
+    }
+
+    #[test]
+    fn switch_profile_during_the_long_break() {
+        let (settings, clock) = (settings(), ManualClock::new());
+        let mut pomo = Pomo::new(&settings, &clock);
+        clock.advance(4 * (25 + 5));
+        pomo.tick(&settings, &clock);
+        assert_eq!(pomo.current(&settings).name, "Napping");
+
+        pomo.set_profile(Some("long".to_string()));
+        let config = settings.with_profile(pomo.profile());
+        assert_eq!(pomo.current(&config).name, "Napping");
+        assert_eq!(remaining_minutes(&pomo, &config, &clock), 15);
+
+        clock.advance(15);
+        let outcome = pomo.tick(&config, &clock);
+        assert!(outcome.records.is_empty());
+        assert_eq!(outcome.event, Some(Event::BreakEnd));
+        assert_eq!(pomo.fields(&config, &clock).round, 1);
+        assert_eq!(remaining_minutes(&pomo, &config, &clock), 50);
+    }
+}
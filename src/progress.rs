@@ -0,0 +1,41 @@
+const FILLED: char = '█';
+const EMPTY: char = '░';
+const DONE: char = '●';
+const TODO: char = '○';
+/// The narrowest progress bar worth drawing.
+const MIN_BAR_WIDTH: usize = 10;
+
+/// The rounds of a set, e.g. `●●○○` when 2 out of 4 are completed.
+fn rounds(completed: usize, rounds: usize) -> String {
+    (0..rounds)
+        .map(|i| if i < completed { DONE } else { TODO })
+        .collect()
+}
+
+/// A bar `width` columns wide, filled up to `progress` percent.
+fn bar(progress: u32, width: usize) -> String {
+    let filled = (width * progress.min(100) as usize + 50) / 100;
+    (0..width)
+        .map(|i| if i < filled { FILLED } else { EMPTY })
+        .collect()
+}
+
+/// The round indicators followed by the progress bar of the current phase,
+/// fitted in `cols` columns: the bar is left out when there isn't enough
+/// room, and so are the indicators.
+pub fn line(completed: usize, total: usize, progress: u32, cols: usize) -> String {
+    let indicators = if total <= cols {
+        rounds(completed, total)
+    } else {
+        format!("{}/{}", completed, total)
+    };
+    let percent = format!(" {:>3}%", progress.min(100));
+    let width = cols.saturating_sub(indicators.chars().count() + 1 + percent.len());
+    if width >= MIN_BAR_WIDTH {
+        format!("{} {}{}", indicators, bar(progress, width), percent)
+    } else if indicators.chars().count() <= cols {
+        indicators
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,325 @@
+//! Versioning of the saved timer state (`/data/pomo.json`).
+//!
+//! Every saved state carries a `version` field, states written before it
+//! was introduced are version 0. Loading a state first brings it up to
+//! [`CURRENT_VERSION`], one migration at a time.
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Map, Value};
+
+/// Version of the state written by this release.
+pub const CURRENT_VERSION: u64 = 2;
+
+type Migration = fn(Map<String, Value>, DateTime<Utc>) -> Result<Map<String, Value>, String>;
+
+/// `MIGRATIONS[n]` turns a version `n` state into a version `n + 1` one.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [from_v0, from_v1];
+
+/// The number of rounds of a version 1 state without a `rounds` field.
+const V1_DEFAULT_ROUNDS: u64 = 4;
+
+/// Bring a saved `state` of any known version up to [`CURRENT_VERSION`],
+/// `now` is when it's loaded.
+pub fn migrate(state: Value, now: DateTime<Utc>) -> Result<Value, String> {
+    let mut state = match state {
+        Value::Object(state) => state,
+        _ => return Err("expect a JSON object".to_string()),
+    };
+    let version = match state.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("invalid version {}", version))?,
+    };
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "unsupported version {}, it's written by a newer release",
+            version
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        state = migration(state, now)?;
+    }
+    state.insert("version".to_string(), CURRENT_VERSION.into());
+    Ok(Value::Object(state))
+}
+
+/// Tag a state with [`CURRENT_VERSION`] before saving it.
+pub fn stamp(mut state: Value) -> Value {
+    if let Value::Object(state) = &mut state {
+        state.insert("version".to_string(), CURRENT_VERSION.into());
+    }
+    state
+}
+
+/// Version 0 kept the remaining time of the phase inside `status` and a
+/// `paused` flag, e.g.
+/// `{"paused":false,"status":{"Working":[0,{"secs":1500,"nanos":0}]}}`.
+///
+/// Version 1 keeps the phase in `status` and its `deadline`, along with when
+/// the timer was paused (`paused_at`) and when the working interval
+/// `started`.
+fn from_v0(
+    mut state: Map<String, Value>,
+    now: DateTime<Utc>,
+) -> Result<Map<String, Value>, String> {
+    let paused = match state.remove("paused") {
+        None => false,
+        Some(paused) => paused
+            .as_bool()
+            .ok_or_else(|| format!("invalid paused flag {}", paused))?,
+    };
+    let status = state
+        .remove("status")
+        .ok_or_else(|| "missing status".to_string())?;
+    let invalid_status = || format!("invalid status {}", status);
+    let (phase, args) = match &status {
+        Value::Object(status) if status.len() == 1 => status.iter().next().unwrap(),
+        _ => return Err(invalid_status()),
+    };
+    let (phase, remaining) = match (phase.as_str(), args) {
+        ("Working", Value::Array(args)) | ("Resting", Value::Array(args)) if args.len() == 2 => {
+            let round = args[0].as_u64().ok_or_else(invalid_status)?;
+            let mut working_or_resting = Map::new();
+            working_or_resting.insert(phase.clone(), round.into());
+            (
+                Value::Object(working_or_resting),
+                duration(&args[1]).ok_or_else(invalid_status)?,
+            )
+        }
+        ("Napping", remaining) => (
+            Value::String("Napping".to_string()),
+            duration(remaining).ok_or_else(invalid_status)?,
+        ),
+        _ => return Err(invalid_status()),
+    };
+
+    let deadline = now
+        .checked_add_signed(remaining)
+        .ok_or_else(invalid_status)?;
+    state.insert("status".to_string(), phase);
+    state.insert("deadline".to_string(), timestamp(deadline));
+    state.insert(
+        "paused_at".to_string(),
+        if paused { timestamp(now) } else { Value::Null },
+    );
+    // The start of the interval wasn't recorded, count it from now on.
+    state.insert("started".to_string(), timestamp(now));
+    Ok(state)
+}
+
+/// Version 1 kept the phase in `status` as in `{"Working":0}`,
+/// `{"Resting":0}` or `"Napping"`, along with the `rounds` of the cycle.
+///
+/// Version 2 keeps the index of the `phase` in the configured cycle, which is
+/// by default `rounds` times working then resting, followed by napping.
+fn from_v1(
+    mut state: Map<String, Value>,
+    _now: DateTime<Utc>,
+) -> Result<Map<String, Value>, String> {
+    let rounds = match state.remove("rounds") {
+        None => V1_DEFAULT_ROUNDS,
+        Some(rounds) => rounds
+            .as_u64()
+            .ok_or_else(|| format!("invalid rounds {}", rounds))?,
+    };
+    let status = state
+        .remove("status")
+        .ok_or_else(|| "missing status".to_string())?;
+    let invalid_status = || format!("invalid status {}", status);
+    let phase = match &status {
+        Value::String(phase) if phase == "Napping" => rounds * 2,
+        Value::Object(status) if status.len() == 1 => {
+            let (phase, round) = status.iter().next().unwrap();
+            let round = round.as_u64().ok_or_else(invalid_status)?;
+            match phase.as_str() {
+                "Working" => round * 2,
+                "Resting" => round * 2 + 1,
+                _ => return Err(invalid_status()),
+            }
+        }
+        _ => return Err(invalid_status()),
+    };
+    state.insert("phase".to_string(), phase.into());
+    Ok(state)
+}
+
+/// Parse a serialized `std::time::Duration`, `{"secs":1500,"nanos":0}`,
+/// `None` if it's out of range.
+fn duration(value: &Value) -> Option<Duration> {
+    let secs = value.get("secs")?.as_u64()?;
+    let nanos = value.get("nanos")?.as_u64()?;
+    if nanos >= 1_000_000_000 {
+        return None;
+    }
+    Duration::from_std(std::time::Duration::new(secs, nanos as u32)).ok()
+}
+
+fn timestamp(t: DateTime<Utc>) -> Value {
+    serde_json::to_value(t).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn now() -> DateTime<Utc> {
+        "2022-06-21T08:00:00Z".parse().unwrap()
+    }
+
+    fn load(fixture: &str) -> Value {
+        migrate(serde_json::from_str(fixture).unwrap(), now()).unwrap()
+    }
+
+    #[test]
+    fn migrate_working_from_v0() {
+        let state = load(include_str!("../tests/fixtures/pomo-v0-working.json"));
+        assert_eq!(
+            state,
+            json!({
+                "version": 2,
+                "phase": 2,
+                "deadline": "2022-06-21T08:20:34.500Z",
+                "paused_at": null,
+                "started": "2022-06-21T08:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn migrate_paused_resting_from_v0() {
+        let state = load(include_str!(
+            "../tests/fixtures/pomo-v0-resting-paused.json"
+        ));
+        assert_eq!(
+            state,
+            json!({
+                "version": 2,
+                "phase": 7,
+                "deadline": "2022-06-21T08:02:00Z",
+                "paused_at": "2022-06-21T08:00:00Z",
+                "started": "2022-06-21T08:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn migrate_napping_from_v0() {
+        let state = load(include_str!("../tests/fixtures/pomo-v0-napping.json"));
+        assert_eq!(
+            state,
+            json!({
+                "version": 2,
+                "phase": 8,
+                "deadline": "2022-06-21T08:15:00Z",
+                "paused_at": null,
+                "started": "2022-06-21T08:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn keep_fields_unknown_to_v0() {
+        let state = migrate(
+            json!({
+                "paused": false,
+                "status": {"Working": [0, {"secs": 60, "nanos": 0}]},
+                "interrupted": true,
+            }),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(state["interrupted"], true);
+    }
+
+    #[test]
+    fn migrate_phases_from_v1() {
+        for (status, rounds, phase) in &[
+            (json!({"Working": 0}), json!(4), 0),
+            (json!({"Resting": 0}), json!(4), 1),
+            (json!({"Working": 2}), json!(3), 4),
+            (json!({"Resting": 2}), json!(3), 5),
+            (json!("Napping"), json!(3), 6),
+            (json!("Napping"), Value::Null, 8),
+        ] {
+            let mut state = json!({
+                "version": 1,
+                "status": status,
+                "deadline": "2022-06-21T08:15:00Z",
+                "paused_at": null,
+                "started": "2022-06-21T07:00:00Z",
+            });
+            if !rounds.is_null() {
+                state["rounds"] = rounds.clone();
+            }
+            assert_eq!(
+                migrate(state, now()).unwrap(),
+                json!({
+                    "version": 2,
+                    "phase": phase,
+                    "deadline": "2022-06-21T08:15:00Z",
+                    "paused_at": null,
+                    "started": "2022-06-21T07:00:00Z",
+                }),
+                "{} with {} rounds",
+                status,
+                rounds
+            );
+        }
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let state = json!({
+            "version": CURRENT_VERSION,
+            "phase": 8,
+            "deadline": "2022-06-21T08:15:00Z",
+            "paused_at": null,
+            "started": "2022-06-21T07:00:00Z",
+        });
+        assert_eq!(migrate(state.clone(), now()).unwrap(), state);
+    }
+
+    #[test]
+    fn stamp_current_version() {
+        assert_eq!(
+            stamp(json!({"phase": 8})),
+            json!({"version": CURRENT_VERSION, "phase": 8})
+        );
+    }
+
+    #[test]
+    fn reject_newer_version() {
+        assert!(migrate(json!({"version": CURRENT_VERSION + 1}), now()).is_err());
+    }
+
+    #[test]
+    fn reject_invalid_v0() {
+        for state in &[
+            json!([]),
+            json!({"paused": false}),
+            json!({"paused": 1, "status": {"Napping": {"secs": 1, "nanos": 0}}}),
+            json!({"paused": false, "status": {"Working": [0]}}),
+            json!({"paused": false, "status": {"Sleeping": {"secs": 1, "nanos": 0}}}),
+            json!({"paused": false, "status": {"Napping": 900}}),
+            json!({"paused": false, "status": {"Napping": {"secs": 100000000000000000u64, "nanos": 0}}}),
+            json!({"paused": false, "status": {"Napping": {"secs": 1, "nanos": 1000000000}}}),
+        ] {
+            assert!(migrate(state.clone(), now()).is_err(), "{}", state);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_v1() {
+        for state in &[
+            json!({"version": 1}),
+            json!({"version": 1, "status": "Working"}),
+            json!({"version": 1, "status": {"Working": -1}}),
+            json!({"version": 1, "status": {"Sleeping": 0}}),
+            json!({"version": 1, "status": "Napping", "rounds": "4"}),
+        ] {
+            assert!(migrate(state.clone(), now()).is_err(), "{}", state);
+        }
+    }
+}
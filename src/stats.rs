@@ -0,0 +1,228 @@
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
+use std::collections::BTreeSet;
+
+use crate::history::Record;
+use crate::timezone::Timezone;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Default, Clone, Copy)]
+struct Tally {
+    count: usize,
+    minutes: u64,
+}
+
+impl Tally {
+    fn add(&mut self, record: &Record) {
+        self.count += 1;
+        self.minutes += record.planned / 60;
+    }
+}
+
+/// Summary of the completed (not interrupted) working intervals.
+pub struct Stats {
+    today: Tally,
+    week: Tally,
+    total: Tally,
+    /// Completed intervals of each day of the current week, from Monday.
+    days: [usize; 7],
+    /// Consecutive days with at least one completed interval, up to today.
+    streak: usize,
+}
+
+impl Stats {
+    pub fn new(records: &[Record], timezone: Timezone, now: DateTime<Utc>) -> Self {
+        let today = timezone.localize(now).naive_local().date();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let mut stats = Stats {
+            today: Tally::default(),
+            week: Tally::default(),
+            total: Tally::default(),
+            days: [0; 7],
+            streak: 0,
+        };
+        let mut active_days = BTreeSet::new();
+
+        for record in records.iter().filter(|r| !r.interrupted) {
+            let day = timezone.localize(record.end).naive_local().date();
+            active_days.insert(day);
+            stats.total.add(record);
+            if day == today {
+                stats.today.add(record);
+            }
+            if day >= monday && day <= today {
+                stats.week.add(record);
+                stats.days[day.weekday().num_days_from_monday() as usize] += 1;
+            }
+        }
+
+        // A streak isn't broken until today is over.
+        let mut day = if active_days.contains(&today) {
+            today
+        } else {
+            today.pred()
+        };
+        while active_days.contains(&day) {
+            stats.streak += 1;
+            day = day.pred();
+        }
+
+        stats
+    }
+
+    /// Lay out the statistics in at most `rows` lines of `cols` columns.
+    pub fn lines(&self, rows: usize, cols: usize) -> Vec<String> {
+        let summary = format!(
+            "Today: {} | Week: {} | Streak: {}d | Total: {} ({})",
+            self.today.count,
+            self.week.count,
+            self.streak,
+            self.total.count,
+            minutes(self.total.minutes),
+        );
+        if rows < 5 {
+            return vec![truncate(&summary, cols)];
+        }
+
+        let mut lines = vec![
+            "Statistics".to_string(),
+            format!(
+                "Today:     {} pomodoros, {}",
+                self.today.count,
+                minutes(self.today.minutes)
+            ),
+            format!(
+                "This week: {} pomodoros, {}",
+                self.week.count,
+                minutes(self.week.minutes)
+            ),
+            format!("Streak:    {} days", self.streak),
+            format!(
+                "Total:     {} pomodoros, {}",
+                self.total.count,
+                minutes(self.total.minutes)
+            ),
+        ];
+        if rows >= lines.len() + 1 + WEEKDAYS.len() {
+            lines.push(String::new());
+            let max = self.days.iter().copied().max().unwrap_or(0).max(1);
+            // "Mon 12 " in front of the bar.
+            let width = cols.saturating_sub(7);
+            for (weekday, count) in WEEKDAYS.iter().zip(self.days.iter()) {
+                lines.push(format!(
+                    "{} {:>2} {}",
+                    weekday,
+                    count,
+                    "#".repeat(count * width / max)
+                ));
+            }
+        }
+
+        lines.iter().map(|line| truncate(line, cols)).collect()
+    }
+}
+
+fn minutes(minutes: u64) -> String {
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+fn truncate(s: &str, cols: usize) -> String {
+    s.chars().take(cols).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    /// Tuesday, 16:00 in `+08:00`.
+    const NOW: &str = "2022-06-21T08:00:00Z";
+
+    fn record(end: &str) -> Record {
+        let end: DateTime<Utc> = end.parse().unwrap();
+        Record {
+            start: end - Duration::minutes(25),
+            end,
+            round: 1,
+            planned: 25 * 60,
+            interrupted: false,
+            task: None,
+            pauses: vec![],
+        }
+    }
+
+    /// Records ending at 06:00 UTC, `days` before the day of [`NOW`].
+    fn days_ago(days: &[i64]) -> Vec<Record> {
+        let morning: DateTime<Utc> = "2022-06-21T06:00:00Z".parse().unwrap();
+        days.iter()
+            .map(|days| record(&(morning - Duration::days(*days)).to_rfc3339()))
+            .collect()
+    }
+
+    fn stats(records: &[Record], timezone: &str) -> Stats {
+        let timezone = Timezone::try_from(timezone.to_string()).unwrap();
+        Stats::new(records, timezone, NOW.parse().unwrap())
+    }
+
+    #[test]
+    fn days_start_at_midnight_in_the_timezone() {
+        // Monday 23:30 and Tuesday 00:30 in `+08:00`, both Monday in UTC.
+        let records = [
+            record("2022-06-20T15:30:00Z"),
+            record("2022-06-20T16:30:00Z"),
+        ];
+
+        let local = stats(&records, "+08:00");
+        assert_eq!((local.today.count, local.week.count), (1, 2));
+        assert_eq!(local.days[..2], [1, 1]);
+        assert_eq!(local.streak, 2);
+
+        let utc = stats(&records, "UTC");
+        assert_eq!((utc.today.count, utc.week.count), (0, 2));
+        assert_eq!(utc.days[..2], [2, 0]);
+        assert_eq!(utc.streak, 1);
+    }
+
+    #[test]
+    fn the_week_starts_on_monday() {
+        let stats = stats(&days_ago(&[0, 1, 2, 8]), "UTC");
+        assert_eq!(stats.week.count, 2);
+        assert_eq!(stats.days, [1, 1, 0, 0, 0, 0, 0]);
+        assert_eq!((stats.total.count, stats.total.minutes), (4, 100));
+    }
+
+    #[test]
+    fn a_gap_breaks_the_streak() {
+        assert_eq!(stats(&days_ago(&[0, 1, 2, 4, 5]), "UTC").streak, 3);
+        assert_eq!(stats(&days_ago(&[0, 0, 2]), "UTC").streak, 1);
+    }
+
+    #[test]
+    fn the_streak_goes_on_until_today_is_over() {
+        assert_eq!(stats(&days_ago(&[1, 2]), "UTC").streak, 2);
+        assert_eq!(stats(&days_ago(&[2, 3]), "UTC").streak, 0);
+        let stats = stats(&days_ago(&[1]), "UTC");
+        assert_eq!((stats.today.count, stats.today.minutes), (0, 0));
+    }
+
+    #[test]
+    fn interrupted_intervals_dont_count() {
+        let mut records = days_ago(&[0, 1]);
+        records[0].interrupted = true;
+        let stats = stats(&records, "UTC");
+        assert_eq!((stats.today.count, stats.total.count), (0, 1));
+        assert_eq!(stats.streak, 1);
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+
+use crate::pomo::Pomo;
+use crate::schema;
+
+const STATE_SAVING_PATH: &str = "/data/pomo.json";
+const BACKUP_PATH: &str = "/data/pomo.json.bak";
+const TEMP_PATH: &str = "/data/pomo.json.tmp";
+
+fn read(path: &str) -> io::Result<Pomo> {
+    let f = fs::File::open(path)?;
+    let state: Value =
+        serde_json::from_reader(f).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    decode(state, Utc::now()).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Bring a saved `state` up to date and restore the timer from it, `now` is
+/// when it's loaded.
+fn decode(state: Value, now: DateTime<Utc>) -> Result<Pomo, String> {
+    let state = schema::migrate(state, now)?;
+    let pomo: Pomo = serde_json::from_value(state).map_err(|e| e.to_string())?;
+    pomo.validate()?;
+    Ok(pomo)
+}
+
+/// Load the saved timer, `None` if there is nothing to restore.
+///
+/// A corrupt state falls back to the backup of the last good one, the
+/// returned message tells what went wrong.
+pub fn load() -> (Option<Pomo>, Option<String>) {
+    match read(STATE_SAVING_PATH) {
+        Ok(pomo) => match fs::copy(STATE_SAVING_PATH, BACKUP_PATH) {
+            Ok(_) => (Some(pomo), None),
+            Err(e) => (
+                Some(pomo),
+                Some(format!("failed to back up {}: {}", STATE_SAVING_PATH, e)),
+            ),
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => (None, None),
+        Err(e) => match read(BACKUP_PATH) {
+            Ok(pomo) => (
+                Some(pomo),
+                Some(format!(
+                    "{}: {}, restored the last good state",
+                    STATE_SAVING_PATH, e
+                )),
+            ),
+            Err(_) => (
+                None,
+                Some(format!("{}: {}, started over", STATE_SAVING_PATH, e)),
+            ),
+        },
+    }
+}
+
+/// Save the timer, the previous state is kept until the new one is
+/// completely written.
+pub fn save(pomo: &Pomo) -> io::Result<()> {
+    let content = serde_json::to_vec(&schema::stamp(serde_json::to_value(pomo)?))?;
+    fs::write(TEMP_PATH, content)?;
+    fs::rename(TEMP_PATH, STATE_SAVING_PATH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::ManualClock;
+    use crate::timer::Clock;
+
+    fn load(fixture: &str, clock: &ManualClock) -> Pomo {
+        decode(serde_json::from_str(fixture).unwrap(), clock.now()).unwrap()
+    }
+
+    #[test]
+    fn restore_working_from_v0() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let pomo = load(
+            include_str!("../tests/fixtures/pomo-v0-working.json"),
+            &clock,
+        );
+        let fields = pomo.fields(&config, &clock);
+        assert_eq!(
+            (fields.phase, fields.round, fields.rounds),
+            ("Working", 2, 4)
+        );
+        assert_eq!(fields.remaining.as_millis(), 1_234_500);
+        assert!(!pomo.paused() && !pomo.waiting());
+    }
+
+    #[test]
+    fn restore_paused_resting_from_v0() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let pomo = load(
+            include_str!("../tests/fixtures/pomo-v0-resting-paused.json"),
+            &clock,
+        );
+        clock.advance(10);
+        let fields = pomo.fields(&config, &clock);
+        assert_eq!((fields.phase, fields.round), ("Resting", 4));
+        assert_eq!(fields.remaining.as_secs(), 120);
+        assert!(pomo.paused());
+    }
+
+    #[test]
+    fn restore_napping_from_v0() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let pomo = load(
+            include_str!("../tests/fixtures/pomo-v0-napping.json"),
+            &clock,
+        );
+        assert_eq!(pomo.current(&config).name, "Napping");
+        assert_eq!(pomo.completed(&config), 4);
+        assert_eq!(pomo.fields(&config, &clock).remaining.as_secs(), 900);
+    }
+
+    #[test]
+    fn round_trip_the_current_version() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let mut pomo = Pomo::new(&config, &clock);
+        pomo.tasks.add("write docs".to_string());
+        clock.advance(35);
+        pomo.tick(&config, &clock);
+        pomo.toggle_pause(&config, &clock);
+
+        let saved = schema::stamp(serde_json::to_value(&pomo).unwrap());
+        let restored = decode(saved, clock.now()).unwrap();
+        assert_eq!(restored.current(&config).name, "Working");
+        assert_eq!(
+            restored.fields(&config, &clock).remaining.as_secs(),
+            20 * 60
+        );
+        assert_eq!(restored.tasks.active().unwrap().completed, 1);
+        assert!(restored.paused());
+    }
+
+    #[test]
+    fn reject_out_of_range_lengths() {
+        let (config, clock) = (Config::default(), ManualClock::new());
+        let saved = schema::stamp(serde_json::to_value(Pomo::new(&config, &clock)).unwrap());
+        for length in &[-1, i64::MAX] {
+            let mut state = saved.clone();
+            state["length"] = (*length).into();
+            assert_eq!(
+                decode(state, clock.now()).err(),
+                Some(format!("invalid length {}", length))
+            );
+        }
+    }
+}
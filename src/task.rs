@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Task {
+    pub name: String,
+    /// Estimated number of pomodoros.
+    pub estimate: usize,
+    /// Number of finished pomodoros.
+    pub completed: usize,
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}/{}", self.name, self.completed, self.estimate)
+    }
+}
+
+/// A small to-do list, finished pomodoros are credited to the active task.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Tasks {
+    list: Vec<Task>,
+    active: Option<usize>,
+}
+
+impl Tasks {
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn active(&self) -> Option<&Task> {
+        self.active.and_then(|i| self.list.get(i))
+    }
+
+    /// Append a task and make it the active one.
+    pub fn add(&mut self, name: String) {
+        self.list.push(Task {
+            name,
+            estimate: 1,
+            completed: 0,
+        });
+        self.active = Some(self.list.len() - 1);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        if i >= self.list.len() {
+            return;
+        }
+        self.list.remove(i);
+        self.active = match self.active {
+            Some(active) if active == i => None,
+            Some(active) if active > i => Some(active - 1),
+            active => active,
+        };
+    }
+
+    /// Make the `i`th task the active one, or no task if it's already active.
+    pub fn toggle_active(&mut self, i: usize) {
+        if i < self.list.len() {
+            self.active = if self.active == Some(i) {
+                None
+            } else {
+                Some(i)
+            };
+        }
+    }
+
+    pub fn adjust_estimate(&mut self, i: usize, increase: bool) {
+        if let Some(task) = self.list.get_mut(i) {
+            task.estimate = if increase {
+                task.estimate + 1
+            } else {
+                task.estimate.saturating_sub(1).max(1)
+            };
+        }
+    }
+
+    /// Credit a finished pomodoro to the active task.
+    pub fn credit(&mut self) {
+        if let Some(task) = self.active.and_then(|i| self.list.get_mut(i)) {
+            task.completed += 1;
+        }
+    }
+
+    /// Lay out the list in at most `rows` lines of `cols` columns, keeping
+    /// the `cursor`ed task in view.
+    pub fn lines(&self, cursor: usize, rows: usize, cols: usize) -> Vec<String> {
+        let first = (cursor + 1).saturating_sub(rows);
+        self.list
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(i, task)| {
+                format!(
+                    "{}{} {}",
+                    if i == cursor { ">" } else { " " },
+                    if Some(i) == self.active { "*" } else { " " },
+                    task
+                )
+                .chars()
+                .take(cols)
+                .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(names: &[&str]) -> Tasks {
+        let mut tasks = Tasks::default();
+        for name in names {
+            tasks.add(name.to_string());
+        }
+        tasks
+    }
+
+    fn active(tasks: &Tasks) -> Option<&str> {
+        tasks.active().map(|task| task.name.as_str())
+    }
+
+    #[test]
+    fn the_added_task_is_active() {
+        let tasks = tasks(&["write docs", "review"]);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(active(&tasks), Some("review"));
+    }
+
+    #[test]
+    fn remove_keeps_the_active_task() {
+        for (removed, left, expected) in &[
+            // Before the active task, which shifts up.
+            (0, 2, Some("review")),
+            // The active task itself.
+            (1, 2, None),
+            // After the active task.
+            (2, 2, Some("review")),
+            // Out of the list.
+            (3, 3, Some("review")),
+        ] {
+            let mut tasks = tasks(&["write docs", "review", "release"]);
+            tasks.toggle_active(1);
+            tasks.remove(*removed);
+            assert_eq!(tasks.len(), *left, "{}", removed);
+            assert_eq!(active(&tasks), *expected, "{}", removed);
+        }
+    }
+
+    #[test]
+    fn toggle_the_active_task() {
+        let mut tasks = tasks(&["write docs", "review"]);
+        tasks.toggle_active(0);
+        assert_eq!(active(&tasks), Some("write docs"));
+        tasks.toggle_active(0);
+        assert_eq!(active(&tasks), None);
+        tasks.toggle_active(2);
+        assert_eq!(active(&tasks), None);
+        tasks.toggle_active(1);
+        assert_eq!(active(&tasks), Some("review"));
+    }
+
+    #[test]
+    fn estimate_at_least_one_pomodoro() {
+        let mut tasks = tasks(&["write docs"]);
+        tasks.adjust_estimate(0, true);
+        tasks.adjust_estimate(0, true);
+        assert_eq!(tasks.active().unwrap().estimate, 3);
+        for _ in 0..3 {
+            tasks.adjust_estimate(0, false);
+        }
+        assert_eq!(tasks.active().unwrap().estimate, 1);
+        // Out of the list.
+        tasks.adjust_estimate(1, true);
+        assert_eq!(tasks.active().unwrap().estimate, 1);
+    }
+
+    #[test]
+    fn credit_the_active_task() {
+        let mut tasks = tasks(&["write docs", "review"]);
+        tasks.credit();
+        assert_eq!(tasks.active().unwrap().to_string(), "review 1/1");
+        tasks.toggle_active(1);
+        tasks.credit();
+        assert_eq!(
+            tasks.lines(0, 2, 80),
+            vec![">  write docs 0/1", "   review 1/1"]
+        );
+    }
+
+    #[test]
+    fn keep_the_cursor_in_view() {
+        let tasks = tasks(&["write docs", "review", "release"]);
+        assert_eq!(tasks.lines(2, 2, 12), vec!["   review 0/", ">* release 0"]);
+        assert_eq!(tasks.lines(0, 2, 80).len(), 2);
+    }
+}
@@ -0,0 +1,362 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// From the most to the least detailed: the date goes first, then the
+/// weekday, then the verbose status, until only the icon and the remaining
+/// time are left.
+const DEFAULT_FORMATS: [&str; 5] = [
+    "{status} | {time:%H:%M %Y-%m-%d %a}",
+    "{status} | {time:%H:%M %a}",
+    "{status} | {time:%H:%M}",
+    "{icon} {round}/{rounds} {remaining}{paused: [paused]} | {time:%H:%M}",
+    "{icon} {remaining}",
+];
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_PAUSED_TEXT: &str = "[paused]";
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Status,
+    Phase,
+    Icon,
+    Remaining,
+    Round,
+    Rounds,
+    Paused(String),
+    Progress,
+    Task,
+    Profile,
+    Pauses,
+    Clock(String),
+}
+
+/// A parsed status line template, e.g. `"{phase} {remaining} | {time:%H:%M}"`.
+///
+/// Placeholders are:
+/// - `{status}`: the full timer status, e.g. `Working(round 1/4): remaining 24:59`.
+/// - `{phase}`: the name of the current phase.
+/// - `{icon}`: a symbol of the current phase.
+/// - `{remaining}`: remaining time of the current phase as `mm:ss`.
+/// - `{round}` and `{rounds}`: the current round and the total rounds of a set.
+/// - `{paused}` or `{paused:TEXT}`: `TEXT` if the timer is paused, nothing otherwise.
+/// - `{progress}`: progress of the current phase in percent.
+/// - `{task}`: the task label, if any.
+/// - `{profile}`: the name of the active profile, if any.
+/// - `{pauses}`: the pauses of the current working interval, e.g. `2 interruptions, 3m paused`.
+/// - `{time:FORMAT}` and `{date:FORMAT}`: current time in strftime `FORMAT`.
+///
+/// `{{` and `}}` produce literal braces.
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "String")]
+pub struct Template(Vec<Segment>);
+
+/// The values a template is evaluated against.
+pub struct Fields<'a> {
+    pub status: String,
+    pub phase: &'a str,
+    pub icon: char,
+    pub remaining: Duration,
+    pub round: usize,
+    pub rounds: usize,
+    pub paused: bool,
+    pub progress: u32,
+    pub now: DateTime<FixedOffset>,
+    pub task: &'a str,
+    pub profile: &'a str,
+    pub pauses: String,
+}
+
+/// Status line templates from the most to the least detailed, the first
+/// one fitting in the pane is used.
+///
+/// It's configured as either a single template or a list of them.
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "Value")]
+pub struct Formats(Vec<Template>);
+
+impl Default for Formats {
+    fn default() -> Self {
+        Formats(
+            DEFAULT_FORMATS
+                .iter()
+                .map(|format| Template::parse(format).unwrap())
+                .collect(),
+        )
+    }
+}
+
+impl TryFrom<Value> for Formats {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let formats = match value {
+            Value::String(format) => vec![Template::parse(&format)?],
+            Value::Array(formats) => formats
+                .iter()
+                .map(|format| match format {
+                    Value::String(format) => Template::parse(format),
+                    _ => Err(format!("expect a template string, got {}", format)),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => {
+                return Err(format!(
+                    "expect a template or a list of them, got {}",
+                    value
+                ))
+            }
+        };
+        if formats.is_empty() {
+            return Err("expect at least one template".to_string());
+        }
+        Ok(Formats(formats))
+    }
+}
+
+impl Formats {
+    /// Render the most detailed template fitting in `cols` columns, the
+    /// least detailed one is cut otherwise.
+    pub fn render(&self, fields: &Fields, cols: usize) -> String {
+        let mut line = String::new();
+        for template in &self.0 {
+            line = template.render(fields);
+            if line.chars().count() <= cols {
+                return line;
+            }
+        }
+        line.chars().take(cols).collect()
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Template::parse(&s)
+    }
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut segments = vec![];
+        let mut text = String::new();
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("unclosed placeholder in \"{}\"", s))?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(text.split_off(0)));
+                    }
+                    segments.push(Segment::parse(&rest[..end])?);
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(format!("unmatched '}}' in \"{}\"", s)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(Template(segments))
+    }
+
+    pub fn render(&self, fields: &Fields) -> String {
+        let mut s = String::new();
+        for segment in &self.0 {
+            // Writing into a String never fails.
+            let _ = match segment {
+                Segment::Text(text) => write!(s, "{}", text),
+                Segment::Status => write!(s, "{}", fields.status),
+                Segment::Phase => write!(s, "{}", fields.phase),
+                Segment::Icon => write!(s, "{}", fields.icon),
+                Segment::Remaining => write!(
+                    s,
+                    "{:02}:{:02}",
+                    fields.remaining.as_secs() / 60,
+                    fields.remaining.as_secs() % 60
+                ),
+                Segment::Round => write!(s, "{}", fields.round),
+                Segment::Rounds => write!(s, "{}", fields.rounds),
+                Segment::Paused(text) if fields.paused => write!(s, "{}", text),
+                Segment::Paused(_) => Ok(()),
+                Segment::Progress => write!(s, "{}", fields.progress),
+                Segment::Task => write!(s, "{}", fields.task),
+                Segment::Profile => write!(s, "{}", fields.profile),
+                Segment::Pauses => write!(s, "{}", fields.pauses),
+                Segment::Clock(format) => write!(s, "{}", fields.now.format(format)),
+            };
+        }
+        s
+    }
+}
+
+impl Segment {
+    fn parse(placeholder: &str) -> Result<Self, String> {
+        let (name, arg) = match placeholder.find(':') {
+            Some(i) => (&placeholder[..i], Some(&placeholder[i + 1..])),
+            None => (placeholder, None),
+        };
+        let segment = match (name.trim(), arg) {
+            ("status", None) => Segment::Status,
+            ("phase", None) => Segment::Phase,
+            ("icon", None) => Segment::Icon,
+            ("remaining", None) => Segment::Remaining,
+            ("round", None) => Segment::Round,
+            ("rounds", None) => Segment::Rounds,
+            ("progress", None) => Segment::Progress,
+            ("task", None) => Segment::Task,
+            ("profile", None) => Segment::Profile,
+            ("pauses", None) => Segment::Pauses,
+            ("paused", text) => Segment::Paused(text.unwrap_or(DEFAULT_PAUSED_TEXT).to_string()),
+            ("time", format) => Segment::clock(format.unwrap_or(DEFAULT_TIME_FORMAT))?,
+            ("date", format) => Segment::clock(format.unwrap_or(DEFAULT_DATE_FORMAT))?,
+            _ => return Err(format!("unknown placeholder \"{{{}}}\"", placeholder)),
+        };
+        Ok(segment)
+    }
+
+    fn clock(format: &str) -> Result<Self, String> {
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(format!("invalid time format \"{}\"", format));
+        }
+        Ok(Segment::Clock(format.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields() -> Fields<'static> {
+        Fields {
+            status: "Working(round 2/4): remaining 04:05".to_string(),
+            phase: "Working",
+            icon: '●',
+            remaining: Duration::from_secs(4 * 60 + 5),
+            round: 2,
+            rounds: 4,
+            paused: false,
+            progress: 84,
+            now: "2022-06-21T10:30:00+02:00".parse().unwrap(),
+            task: "write docs",
+            profile: "deep work",
+            pauses: "1 interruption, 3m paused".to_string(),
+        }
+    }
+
+    fn render(template: &str, fields: &Fields) -> String {
+        Template::parse(template).unwrap().render(fields)
+    }
+
+    #[test]
+    fn render_every_placeholder() {
+        let fields = fields();
+        for (template, line) in &[
+            ("{status}", "Working(round 2/4): remaining 04:05"),
+            ("{icon} {phase} {round}/{rounds}", "● Working 2/4"),
+            ("{remaining} {progress}%", "04:05 84%"),
+            (
+                "[{task}] <{profile}> ({pauses})",
+                "[write docs] <deep work> (1 interruption, 3m paused)",
+            ),
+            ("{time} {date}", "10:30 2022-06-21"),
+            ("{time:%H:%M:%S} {date:%a %d}", "10:30:00 Tue 21"),
+            ("{ phase }", "Working"),
+        ] {
+            assert_eq!(render(template, &fields), *line, "{}", template);
+        }
+    }
+
+    #[test]
+    fn paused_only_shows_while_paused() {
+        let mut fields = fields();
+        assert_eq!(render("{remaining}{paused}", &fields), "04:05");
+        assert_eq!(render("{remaining}{paused: (on hold)}", &fields), "04:05");
+        fields.paused = true;
+        assert_eq!(render("{remaining} {paused}", &fields), "04:05 [paused]");
+        assert_eq!(
+            render("{remaining}{paused: (on hold)}", &fields),
+            "04:05 (on hold)"
+        );
+    }
+
+    #[test]
+    fn escape_braces() {
+        assert_eq!(render("{{{phase}}} }}{{", &fields()), "{Working} }{");
+    }
+
+    #[test]
+    fn reject_invalid_templates() {
+        for (template, error) in &[
+            ("{nope}", "unknown placeholder \"{nope}\""),
+            ("{phase:x}", "unknown placeholder \"{phase:x}\""),
+            ("{phase", "unclosed placeholder in \"{phase\""),
+            ("phase}", "unmatched '}' in \"phase}\""),
+            ("{time:%Q}", "invalid time format \"%Q\""),
+        ] {
+            assert_eq!(Template::parse(template).err().as_deref(), Some(*error));
+        }
+    }
+
+    #[test]
+    fn fall_back_to_the_first_format_fitting() {
+        let formats: Formats =
+            serde_json::from_value(json!(["{status}", "{phase} {remaining}", "{remaining}"]))
+                .unwrap();
+        let fields = fields();
+        assert_eq!(formats.render(&fields, 80), fields.status);
+        assert_eq!(formats.render(&fields, 13), "Working 04:05");
+        assert_eq!(formats.render(&fields, 12), "04:05");
+        assert_eq!(formats.render(&fields, 3), "04:");
+    }
+
+    #[test]
+    fn default_formats_drop_details_first() {
+        let (formats, fields) = (Formats::default(), fields());
+        assert_eq!(
+            formats.render(&fields, 80),
+            "Working(round 2/4): remaining 04:05 | 10:30 2022-06-21 Tue"
+        );
+        assert_eq!(
+            formats.render(&fields, 45),
+            "Working(round 2/4): remaining 04:05 | 10:30"
+        );
+        assert_eq!(formats.render(&fields, 20), "● 2/4 04:05 | 10:30");
+        assert_eq!(formats.render(&fields, 10), "● 04:05");
+    }
+
+    #[test]
+    fn configure_one_or_several_formats() {
+        let one: Formats = serde_json::from_value(json!("{phase}")).unwrap();
+        assert_eq!(one.render(&fields(), 80), "Working");
+        for (value, error) in &[
+            (json!([]), "expect at least one template"),
+            (json!(["{phase}", 1]), "expect a template string, got 1"),
+            (json!(1), "expect a template or a list of them, got 1"),
+        ] {
+            let result: Result<Formats, _> = serde_json::from_value(value.clone());
+            assert_eq!(result.err().unwrap().to_string(), *error);
+        }
+    }
+}
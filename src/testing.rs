@@ -0,0 +1,33 @@
+//! Helpers shared by the unit tests.
+
+use chrono::{DateTime, Duration, Utc};
+use std::cell::Cell;
+
+use crate::timer::Clock;
+
+/// When every [`ManualClock`] starts.
+const EPOCH: &str = "2022-06-21T08:00:00Z";
+
+/// A clock that only moves when told to.
+pub struct ManualClock(Cell<DateTime<Utc>>);
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock(Cell::new(EPOCH.parse().unwrap()))
+    }
+
+    pub fn advance(&self, minutes: i64) {
+        self.0.set(self.0.get() + Duration::minutes(minutes));
+    }
+
+    /// The time `minutes` after the clock started.
+    pub fn at(&self, minutes: i64) -> DateTime<Utc> {
+        EPOCH.parse::<DateTime<Utc>>().unwrap() + Duration::minutes(minutes)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.get()
+    }
+}
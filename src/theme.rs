@@ -0,0 +1,152 @@
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+use crate::phase::Phase;
+use crate::timer::Kind;
+
+const DIM: &str = "\u{1b}[2m";
+const RESET: &str = "\u{1b}[0m";
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// A terminal foreground color.
+///
+/// It's either one of the 8 basic color names, optionally prefixed with
+/// `bright-`, an index of the 256-color palette such as `"208"`, or an RGB
+/// value such as `"#ff8800"`.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let s = s.trim().to_ascii_lowercase();
+        let (bright, name) = match s.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, s.as_str()),
+        };
+        if let Some(i) = NAMES.iter().position(|n| *n == name) {
+            return Ok(Color::Indexed(bright + i as u8));
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("invalid color \"{}\", expect e.g. \"#ff8800\"", s)),
+            };
+        }
+        s.parse()
+            .map(Color::Indexed)
+            .map_err(|_| format!("unknown color \"{}\"", s))
+    }
+}
+
+impl Color {
+    /// The escape sequence switching to this color.
+    fn escape(self) -> String {
+        match self {
+            Color::Indexed(i) if i < 8 => format!("\u{1b}[{}m", 30 + i),
+            Color::Indexed(i) if i < 16 => format!("\u{1b}[{}m", 90 + i - 8),
+            Color::Indexed(i) => format!("\u{1b}[38;5;{}m", i),
+            Color::Rgb(r, g, b) => format!("\u{1b}[38;2;{};{};{}m", r, g, b),
+        }
+    }
+}
+
+/// The `theme` section of the configuration, `null` leaves a phase
+/// uncolored.
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Turns all the styling off when false.
+    enabled: bool,
+    working: Option<Color>,
+    resting: Option<Color>,
+    napping: Option<Color>,
+    /// Whether the timer is dimmed while paused.
+    dim_paused: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            enabled: true,
+            working: Some(Color::Indexed(1)),
+            resting: Some(Color::Indexed(2)),
+            napping: Some(Color::Indexed(4)),
+            dim_paused: true,
+        }
+    }
+}
+
+impl Theme {
+    /// Style a `line` showing the timer in `phase`.
+    pub fn paint(&self, line: &str, phase: &Phase, paused: bool) -> String {
+        if !self.enabled {
+            return line.to_string();
+        }
+        let color = phase.color.or(match phase.kind {
+            Kind::Work => self.working,
+            Kind::Break => self.resting,
+            Kind::LongBreak => self.napping,
+        });
+        let mut style = color.map(Color::escape).unwrap_or_default();
+        if paused && self.dim_paused {
+            style += DIM;
+        }
+        if style.is_empty() {
+            return line.to_string();
+        }
+        format!("{}{}{}", style, line, RESET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_colors() {
+        for (color, expected) in &[
+            ("red", Ok("\u{1b}[31m")),
+            (" White ", Ok("\u{1b}[37m")),
+            ("bright-black", Ok("\u{1b}[90m")),
+            ("Bright-Cyan", Ok("\u{1b}[96m")),
+            ("3", Ok("\u{1b}[33m")),
+            ("12", Ok("\u{1b}[94m")),
+            ("208", Ok("\u{1b}[38;5;208m")),
+            ("#ff8800", Ok("\u{1b}[38;2;255;136;0m")),
+            ("#00FF7f", Ok("\u{1b}[38;2;0;255;127m")),
+            ("orange", Err("unknown color \"orange\"")),
+            ("bright-208", Err("unknown color \"bright-208\"")),
+            ("256", Err("unknown color \"256\"")),
+            ("-1", Err("unknown color \"-1\"")),
+            ("", Err("unknown color \"\"")),
+            (
+                "#ff88",
+                Err("invalid color \"#ff88\", expect e.g. \"#ff8800\""),
+            ),
+            (
+                "#ff88000",
+                Err("invalid color \"#ff88000\", expect e.g. \"#ff8800\""),
+            ),
+            (
+                "#gg8800",
+                Err("invalid color \"#gg8800\", expect e.g. \"#ff8800\""),
+            ),
+        ] {
+            let escape = Color::try_from(color.to_string()).map(Color::escape);
+            let expected = expected.map(str::to_string).map_err(str::to_string);
+            assert_eq!(escape, expected, "{}", color);
+        }
+    }
+}
@@ -0,0 +1,913 @@
+//! The timing core: which phase the timer is in and when it ends.
+//!
+//! It doesn't read the system time nor notify anyone by itself, the time
+//! comes from a [`Clock`] and the phase changes are returned as [`Event`]s
+//! for the caller to act upon.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The longest a phase may be planned for, in seconds, as much as a
+/// `Duration` holds.
+const MAX_LENGTH: i64 = i64::MAX / 1000;
+
+/// Where the current time comes from.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock.
+#[derive(Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// What a phase is for, which decides how it's recorded, notified and
+/// colored.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// A working interval, recorded in the history and credited to the
+    /// active task.
+    Work,
+    #[default]
+    Break,
+    /// The break ending a set, e.g. the classic 15 minutes one.
+    LongBreak,
+}
+
+/// The phase changes worth telling the user about.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Event {
+    /// A working interval is over, a short break begins.
+    WorkEnd,
+    /// A break is over, a working interval begins.
+    BreakEnd,
+    /// The last short break of a set is over, the long break begins.
+    LongBreakStart,
+}
+
+impl Kind {
+    /// The event of entering a phase of this kind.
+    pub fn event(self) -> Event {
+        match self {
+            Kind::Work => Event::BreakEnd,
+            Kind::Break => Event::WorkEnd,
+            Kind::LongBreak => Event::LongBreakStart,
+        }
+    }
+}
+
+/// A phase of the cycle, as far as timing is concerned.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub kind: Kind,
+    pub length: Duration,
+    /// Whether it starts by itself once the previous phase ends.
+    pub auto_start: bool,
+}
+
+/// A pause of the timer.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Pause {
+    pub start: DateTime<Utc>,
+    /// How long it lasted.
+    pub seconds: i64,
+}
+
+/// A working interval that is over.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Interval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// The round of the interval, counted from 1.
+    pub round: usize,
+    /// The planned length of the interval, in seconds.
+    pub planned: u64,
+    /// Whether it has been paused, skipped or reset, or went by partly
+    /// unattended.
+    pub interrupted: bool,
+    /// When it was paused and for how long.
+    pub pauses: Vec<Pause>,
+}
+
+/// What happened while advancing the timer.
+#[derive(Default, PartialEq, Debug)]
+pub struct Outcome {
+    /// The working intervals that are over.
+    pub intervals: Vec<Interval>,
+    /// The phase change to tell the user about, if any.
+    pub event: Option<Event>,
+}
+
+/// The timer, driven by the wall clock so that it keeps running while the
+/// plugin is hidden or the machine is suspended.
+///
+/// Every method takes the `cycle` of phases it runs through, which may
+/// change between calls: the running phase keeps its kind and length, the
+/// new cycle applies from the next phase on.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Timer {
+    /// The index of the current phase in the cycle it started in.
+    phase: usize,
+    /// The kind of the current phase. States saved before it was
+    /// introduced don't have it.
+    #[serde(default)]
+    kind: Option<Kind>,
+    /// When the current phase ends, as long as the timer isn't paused.
+    deadline: DateTime<Utc>,
+    /// When the timer was paused, if it is.
+    paused_at: Option<DateTime<Utc>>,
+    /// Whether the current phase waits for the user to start it, it's held
+    /// from `paused_at` on.
+    #[serde(default)]
+    waiting: bool,
+    /// When the current working interval started.
+    started: DateTime<Utc>,
+    /// Whether the current working interval has been paused.
+    #[serde(default)]
+    interrupted: bool,
+    /// The pauses of the current phase, but the ongoing one.
+    #[serde(default)]
+    pauses: Vec<Pause>,
+    /// The planned length of the current phase in seconds. States saved
+    /// before it was introduced don't have it.
+    #[serde(default)]
+    length: Option<i64>,
+}
+
+impl Timer {
+    /// A timer running the first phase of `cycle`, which mustn't be empty.
+    pub fn new(cycle: &[Step], clock: &impl Clock) -> Self {
+        let now = clock.now();
+        let length = cycle[0].length;
+        Timer {
+            phase: 0,
+            kind: Some(cycle[0].kind),
+            deadline: now + length,
+            paused_at: None,
+            waiting: false,
+            started: now,
+            interrupted: false,
+            pauses: Vec::new(),
+            length: Some(length.num_seconds()),
+        }
+    }
+
+    /// Check a restored timer, which may have been tampered with.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.length {
+            Some(length) if !(0..=MAX_LENGTH).contains(&length) => {
+                Err(format!("invalid length {}", length))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The index of the current phase. If the cycle changed since the
+    /// phase started, it's the first phase of its kind in `cycle`, or the
+    /// last phase if there is none.
+    pub fn index(&self, cycle: &[Step]) -> usize {
+        let kind = self.kind(cycle);
+        match cycle.get(self.phase) {
+            Some(step) if step.kind == kind => self.phase,
+            _ => cycle
+                .iter()
+                .position(|step| step.kind == kind)
+                .unwrap_or(cycle.len() - 1),
+        }
+    }
+
+    fn next(&self, cycle: &[Step]) -> usize {
+        (self.index(cycle) + 1) % cycle.len()
+    }
+
+    pub fn kind(&self, cycle: &[Step]) -> Kind {
+        self.kind
+            .unwrap_or_else(|| cycle[self.phase.min(cycle.len() - 1)].kind)
+    }
+
+    fn working(&self, cycle: &[Step]) -> bool {
+        self.kind(cycle) == Kind::Work
+    }
+
+    /// The planned length of the current phase.
+    pub fn length(&self, cycle: &[Step]) -> Duration {
+        self.length
+            .map(Duration::seconds)
+            .unwrap_or_else(|| cycle[self.phase.min(cycle.len() - 1)].length)
+    }
+
+    /// The working phases of the cycle before the current one.
+    pub fn completed(&self, cycle: &[Step]) -> usize {
+        cycle[..self.index(cycle)]
+            .iter()
+            .filter(|step| step.kind == Kind::Work)
+            .count()
+    }
+
+    /// The current round and the number of rounds, that is working phases,
+    /// of the cycle.
+    pub fn rounds(&self, cycle: &[Step]) -> (usize, usize) {
+        let rounds = cycle.iter().filter(|step| step.kind == Kind::Work).count();
+        let round = self.completed(cycle) + usize::from(self.working(cycle));
+        (round.max(1).min(rounds), rounds)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused_at.is_some() && !self.waiting
+    }
+
+    pub fn waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// When the ongoing pause started, `None` unless the user paused the
+    /// timer.
+    pub fn paused_since(&self) -> Option<DateTime<Utc>> {
+        self.paused_at.filter(|_| !self.waiting)
+    }
+
+    /// The pauses of the current working interval, including the ongoing
+    /// one which lasts until now. Breaks don't keep track of them.
+    pub fn pauses(&self, cycle: &[Step], clock: &impl Clock) -> Vec<Pause> {
+        let mut pauses = self.pauses.clone();
+        if let (true, false, Some(start)) = (self.working(cycle), self.waiting, self.paused_at) {
+            pauses.push(Pause {
+                start,
+                seconds: (clock.now() - start).num_seconds(),
+            });
+        }
+        pauses
+    }
+
+    pub fn remaining(&self, clock: &impl Clock) -> std::time::Duration {
+        (self.deadline - self.paused_at.unwrap_or_else(|| clock.now()))
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// How much of the current phase is over, in percent.
+    pub fn progress(&self, cycle: &[Step], clock: &impl Clock) -> u32 {
+        let length = self.length(cycle).num_seconds().max(1) as u64;
+        let elapsed = length.saturating_sub(self.remaining(clock).as_secs());
+        (elapsed * 100 / length) as u32
+    }
+
+    /// Catch up with the clock, replaying every phase that ended in the
+    /// meantime, up to the first one waiting for the user.
+    ///
+    /// Phases go on as usual while the pane is hidden, but a gap longer than
+    /// the whole cycle means nobody was there, e.g. the machine was
+    /// suspended: the working intervals that ran entirely in it aren't
+    /// recorded, and the one it ends in is interrupted.
+    pub fn tick(&mut self, cycle: &[Step], clock: &impl Clock) -> Outcome {
+        let mut outcome = Outcome::default();
+        let now = clock.now();
+        if self.paused_at.is_some() || self.deadline > now {
+            return outcome;
+        }
+        let cycle_length = cycle
+            .iter()
+            .fold(Duration::zero(), |length, step| length + step.length);
+        let unattended = now - self.deadline > cycle_length;
+        let mut replaying = false;
+        while !self.waiting && self.deadline <= now {
+            let at = self.deadline;
+            let next = self.next(cycle);
+            let interval = self.switch(next, at, cycle);
+            if !(unattended && replaying) {
+                outcome.intervals.extend(interval);
+            }
+            if unattended && self.working(cycle) {
+                self.interrupted = true;
+            }
+            replaying = true;
+            if !cycle[next].auto_start {
+                self.waiting = true;
+                self.paused_at = Some(at);
+            }
+        }
+        // Only the phase we ended up in is worth telling.
+        outcome.event = Some(self.kind(cycle).event());
+        outcome
+    }
+
+    /// Finish the current phase right away, the next one starts at once.
+    pub fn skip(&mut self, cycle: &[Step], clock: &impl Clock) -> Outcome {
+        let now = clock.now();
+        if self.working(cycle) {
+            self.interrupted = true;
+        }
+        if self.waiting {
+            self.paused_at = None;
+        } else if self.paused() {
+            // The pause goes on in the next phase.
+            self.close_pause(now, cycle);
+            self.paused_at = Some(now);
+        }
+        let next = self.next(cycle);
+        let interval = self.switch(next, now, cycle);
+        Outcome {
+            intervals: interval.into_iter().collect(),
+            event: Some(self.kind(cycle).event()),
+        }
+    }
+
+    /// Push the end of the current phase back by `by`, it's planned that
+    /// much longer.
+    pub fn extend(&mut self, cycle: &[Step], by: Duration) {
+        self.length = Some((self.length(cycle) + by).num_seconds());
+        self.deadline = self.deadline + by;
+    }
+
+    /// Enter the phase `index` at `at`, returns the working interval it
+    /// finishes, if any.
+    fn switch(&mut self, index: usize, at: DateTime<Utc>, cycle: &[Step]) -> Option<Interval> {
+        // A working interval that never started isn't worth a record.
+        let interval = if self.working(cycle) && !self.waiting {
+            Some(self.interval(at, cycle))
+        } else {
+            None
+        };
+        self.pauses.clear();
+        let step = &cycle[index];
+        self.phase = index;
+        self.kind = Some(step.kind);
+        self.length = Some(step.length.num_seconds());
+        if self.working(cycle) {
+            self.started = at;
+            self.interrupted = false;
+        }
+        self.deadline = at + step.length;
+        self.waiting = false;
+        interval
+    }
+
+    /// Give up on the current phase at `at`, it's restarted from scratch
+    /// once the user starts it. Returns the working interval it abandons,
+    /// if any.
+    pub fn abandon(&mut self, cycle: &[Step], at: DateTime<Utc>) -> Option<Interval> {
+        if self.working(cycle) {
+            self.interrupted = true;
+        }
+        self.close_pause(at, cycle);
+        let index = self.index(cycle);
+        let interval = self.switch(index, at, cycle);
+        self.waiting = true;
+        self.paused_at = Some(at);
+        interval
+    }
+
+    /// Start over, returns the working interval it abandons, if any.
+    pub fn reset(&mut self, cycle: &[Step], clock: &impl Clock) -> Option<Interval> {
+        let elapsed = self.remaining(clock) < self.length(cycle).to_std().unwrap_or_default();
+        let interval = if self.working(cycle) && !self.waiting && elapsed {
+            self.interrupted = true;
+            self.close_pause(clock.now(), cycle);
+            Some(self.interval(clock.now(), cycle))
+        } else {
+            None
+        };
+        *self = Timer::new(cycle, clock);
+        interval
+    }
+
+    fn interval(&self, end: DateTime<Utc>, cycle: &[Step]) -> Interval {
+        Interval {
+            start: self.started,
+            end,
+            round: self.rounds(cycle).0,
+            planned: self.length(cycle).num_seconds() as u64,
+            interrupted: self.interrupted,
+            pauses: self.pauses.clone(),
+        }
+    }
+
+    /// Resume at `now` if paused, the pause is kept track of while working.
+    fn close_pause(&mut self, now: DateTime<Utc>, cycle: &[Step]) {
+        if let (false, Some(start)) = (self.waiting, self.paused_at.take()) {
+            self.deadline = self.deadline + (now - start);
+            if self.working(cycle) {
+                self.pauses.push(Pause {
+                    start,
+                    seconds: (now - start).num_seconds(),
+                });
+            }
+        }
+    }
+
+    /// Start the phase waiting for the user.
+    pub fn start(&mut self, cycle: &[Step], clock: &impl Clock) {
+        if let (true, Some(paused_at)) = (self.waiting, self.paused_at.take()) {
+            let now = clock.now();
+            self.deadline = self.deadline + (now - paused_at);
+            self.waiting = false;
+            if self.working(cycle) {
+                self.started = now;
+            }
+        }
+    }
+
+    pub fn toggle_pause(&mut self, cycle: &[Step], clock: &impl Clock) {
+        if self.waiting {
+            return self.start(cycle, clock);
+        }
+        match self.paused_at {
+            Some(_) => self.close_pause(clock.now(), cycle),
+            None => {
+                self.paused_at = Some(clock.now());
+                if self.working(cycle) {
+                    self.interrupted = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ManualClock;
+
+    fn step(kind: Kind, minutes: i64) -> Step {
+        Step {
+            kind,
+            length: Duration::minutes(minutes),
+            auto_start: true,
+        }
+    }
+
+    /// 2 rounds of 25 minutes working and 5 resting, then 15 napping.
+    fn classic() -> Vec<Step> {
+        vec![
+            step(Kind::Work, 25),
+            step(Kind::Break, 5),
+            step(Kind::Work, 25),
+            step(Kind::Break, 5),
+            step(Kind::LongBreak, 15),
+        ]
+    }
+
+    fn remaining_minutes(timer: &Timer, clock: &ManualClock) -> u64 {
+        timer.remaining(clock).as_secs() / 60
+    }
+
+    #[test]
+    fn start_with_the_first_phase() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let timer = Timer::new(&cycle, &clock);
+        assert_eq!(timer.index(&cycle), 0);
+        assert_eq!(timer.kind(&cycle), Kind::Work);
+        assert_eq!(timer.rounds(&cycle), (1, 2));
+        assert_eq!(remaining_minutes(&timer, &clock), 25);
+        assert!(!timer.paused() && !timer.waiting());
+    }
+
+    #[test]
+    fn nothing_happens_before_the_deadline() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(24);
+        assert_eq!(timer.tick(&cycle, &clock), Outcome::default());
+        assert_eq!(remaining_minutes(&timer, &clock), 1);
+        assert_eq!(timer.progress(&cycle, &clock), 96);
+    }
+
+    #[test]
+    fn work_ends_with_a_break() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25);
+        assert_eq!(
+            timer.tick(&cycle, &clock),
+            Outcome {
+                intervals: vec![Interval {
+                    start: clock.at(0),
+                    end: clock.at(25),
+                    round: 1,
+                    planned: 25 * 60,
+                    interrupted: false,
+                    pauses: vec![],
+                }],
+                event: Some(Event::WorkEnd),
+            }
+        );
+        assert_eq!(timer.kind(&cycle), Kind::Break);
+        assert_eq!(timer.rounds(&cycle), (1, 2));
+        assert_eq!(timer.completed(&cycle), 1);
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+    }
+
+    #[test]
+    fn break_ends_with_the_next_round() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25);
+        timer.tick(&cycle, &clock);
+        clock.advance(5);
+        assert_eq!(
+            timer.tick(&cycle, &clock),
+            Outcome {
+                intervals: vec![],
+                event: Some(Event::BreakEnd),
+            }
+        );
+        assert_eq!(timer.index(&cycle), 2);
+        assert_eq!(timer.rounds(&cycle), (2, 2));
+        assert_eq!(timer.completed(&cycle), 1);
+    }
+
+    #[test]
+    fn last_break_rolls_over_to_the_long_break_then_the_first_round() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        for minutes in &[25, 5, 25] {
+            clock.advance(*minutes);
+            timer.tick(&cycle, &clock);
+        }
+        clock.advance(5);
+        assert_eq!(
+            timer.tick(&cycle, &clock).event,
+            Some(Event::LongBreakStart)
+        );
+        assert_eq!(timer.kind(&cycle), Kind::LongBreak);
+        assert_eq!(timer.rounds(&cycle), (2, 2));
+        assert_eq!(timer.completed(&cycle), 2);
+        assert_eq!(remaining_minutes(&timer, &clock), 15);
+
+        clock.advance(15);
+        assert_eq!(timer.tick(&cycle, &clock).event, Some(Event::BreakEnd));
+        assert_eq!(timer.index(&cycle), 0);
+        assert_eq!(timer.rounds(&cycle), (1, 2));
+        assert_eq!(timer.completed(&cycle), 0);
+    }
+
+    #[test]
+    fn replay_the_phases_missed_in_one_go() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        // Both rounds, plus 2 minutes of the long break.
+        clock.advance(25 + 5 + 25 + 5 + 2);
+        let outcome = timer.tick(&cycle, &clock);
+        let ends: Vec<_> = outcome.intervals.iter().map(|i| i.end).collect();
+        assert_eq!(ends, vec![clock.at(25), clock.at(55)]);
+        assert_eq!(outcome.event, Some(Event::LongBreakStart));
+        assert_eq!(remaining_minutes(&timer, &clock), 13);
+    }
+
+    #[test]
+    fn replayed_working_intervals_are_kept_while_hidden() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        // Both rounds and the long break, then 10 minutes of the next round.
+        clock.advance(25 + 5 + 25 + 5 + 15 + 10);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.intervals.len(), 2);
+        assert!(outcome.intervals.iter().all(|i| !i.interrupted));
+        clock.advance(15);
+        let outcome = timer.tick(&cycle, &clock);
+        assert!(!outcome.intervals[0].interrupted);
+    }
+
+    #[test]
+    fn working_intervals_of_a_long_absence_arent_recorded() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        // A night long suspend of ten whole cycles, ending 10 minutes into
+        // the first round.
+        clock.advance(10 * 75 + 10);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.intervals.len(), 1);
+        assert_eq!(outcome.intervals[0].end, clock.at(25));
+        assert!(!outcome.intervals[0].interrupted);
+        // The round it ends in went by partly unattended.
+        assert_eq!(remaining_minutes(&timer, &clock), 15);
+        clock.advance(15);
+        let outcome = timer.tick(&cycle, &clock);
+        assert!(outcome.intervals[0].interrupted);
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_phase_to_start() {
+        let (mut cycle, clock) = (classic(), ManualClock::new());
+        cycle[2].auto_start = false;
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(12 * 60);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.intervals.len(), 1);
+        assert_eq!(outcome.event, Some(Event::BreakEnd));
+        assert!(timer.waiting());
+        assert_eq!(timer.index(&cycle), 2);
+        assert_eq!(remaining_minutes(&timer, &clock), 25);
+    }
+
+    #[test]
+    fn pause_freezes_the_remaining_time() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(10);
+        timer.toggle_pause(&cycle, &clock);
+        assert!(timer.paused());
+        clock.advance(60);
+        assert_eq!(timer.tick(&cycle, &clock), Outcome::default());
+        assert_eq!(remaining_minutes(&timer, &clock), 15);
+
+        timer.toggle_pause(&cycle, &clock);
+        assert!(!timer.paused());
+        clock.advance(15);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.event, Some(Event::WorkEnd));
+        assert_eq!(outcome.intervals[0].end, clock.at(85));
+        assert!(outcome.intervals[0].interrupted);
+    }
+
+    #[test]
+    fn account_for_the_pauses_of_a_working_interval() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(5);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(2);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(5);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(3);
+        assert_eq!(
+            timer.pauses(&cycle, &clock),
+            vec![
+                Pause {
+                    start: clock.at(5),
+                    seconds: 120,
+                },
+                Pause {
+                    start: clock.at(12),
+                    seconds: 180,
+                },
+            ]
+        );
+
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(15);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.intervals[0].pauses.len(), 2);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+    }
+
+    #[test]
+    fn close_the_ongoing_pause_of_a_skipped_or_reset_interval() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(5);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(4);
+        let outcome = timer.skip(&cycle, &clock);
+        assert_eq!(
+            outcome.intervals[0].pauses,
+            vec![Pause {
+                start: clock.at(5),
+                seconds: 240,
+            }]
+        );
+        // The pause goes on during the break, which doesn't count it.
+        clock.advance(1);
+        assert!(timer.paused());
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(5);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(1);
+        let interval = timer.reset(&cycle, &clock).unwrap();
+        assert_eq!(interval.pauses[0].seconds, 60);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+    }
+
+    #[test]
+    fn waiting_isnt_a_pause() {
+        let (mut cycle, clock) = (classic(), ManualClock::new());
+        cycle[1].auto_start = false;
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(30);
+        timer.tick(&cycle, &clock);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+    }
+
+    #[test]
+    fn pauses_of_breaks_arent_kept_track_of() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25);
+        timer.tick(&cycle, &clock);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(1);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+        timer.toggle_pause(&cycle, &clock);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+    }
+
+    #[test]
+    fn pausing_a_break_doesnt_interrupt_the_next_round() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25);
+        timer.tick(&cycle, &clock);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(1);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(5);
+        timer.tick(&cycle, &clock);
+        clock.advance(25);
+        let outcome = timer.tick(&cycle, &clock);
+        assert!(!outcome.intervals[0].interrupted);
+        assert_eq!(outcome.intervals[0].start, clock.at(31));
+    }
+
+    #[test]
+    fn skip_work_records_an_interrupted_interval() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(10);
+        let outcome = timer.skip(&cycle, &clock);
+        assert_eq!(outcome.event, Some(Event::WorkEnd));
+        assert_eq!(outcome.intervals[0].end, clock.at(10));
+        assert!(outcome.intervals[0].interrupted);
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+    }
+
+    #[test]
+    fn skip_while_paused_stays_paused() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(10);
+        timer.skip(&cycle, &clock);
+        assert!(timer.paused());
+        clock.advance(10);
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+    }
+
+    #[test]
+    fn extend_the_current_phase() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        timer.extend(&cycle, Duration::minutes(5));
+        assert_eq!(timer.length(&cycle), Duration::minutes(30));
+        clock.advance(3);
+        assert_eq!(timer.progress(&cycle, &clock), 10);
+        clock.advance(22);
+        assert_eq!(timer.tick(&cycle, &clock), Outcome::default());
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+        clock.advance(5);
+        assert_eq!(timer.tick(&cycle, &clock).intervals[0].planned, 30 * 60);
+    }
+
+    #[test]
+    fn reset_an_extended_interval_early() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        timer.extend(&cycle, Duration::minutes(5));
+        clock.advance(3);
+        let interval = timer.reset(&cycle, &clock).unwrap();
+        assert_eq!(interval.planned, 30 * 60);
+        assert!(interval.interrupted);
+    }
+
+    #[test]
+    fn reset_abandons_the_working_interval() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25 + 5 + 10);
+        timer.tick(&cycle, &clock);
+        let interval = timer.reset(&cycle, &clock).unwrap();
+        assert_eq!((interval.start, interval.end), (clock.at(30), clock.at(40)));
+        assert_eq!(interval.round, 2);
+        assert!(interval.interrupted);
+        assert_eq!(timer.index(&cycle), 0);
+        assert_eq!(remaining_minutes(&timer, &clock), 25);
+    }
+
+    #[test]
+    fn reset_records_nothing_outside_working_intervals() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        assert_eq!(timer.reset(&cycle, &clock), None);
+        clock.advance(26);
+        timer.tick(&cycle, &clock);
+        assert_eq!(timer.reset(&cycle, &clock), None);
+        assert_eq!(timer.kind(&cycle), Kind::Work);
+    }
+
+    #[test]
+    fn abandon_a_paused_working_interval() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25 + 5 + 10);
+        timer.tick(&cycle, &clock);
+        timer.toggle_pause(&cycle, &clock);
+        assert_eq!(timer.paused_since(), Some(clock.at(40)));
+        clock.advance(60);
+        let interval = timer.abandon(&cycle, clock.at(70)).unwrap();
+        assert_eq!((interval.start, interval.end), (clock.at(30), clock.at(70)));
+        assert!(interval.interrupted);
+        assert_eq!(interval.pauses[0].seconds, 30 * 60);
+
+        assert_eq!(timer.index(&cycle), 2);
+        assert!(timer.waiting() && timer.paused_since().is_none());
+        timer.start(&cycle, &clock);
+        assert_eq!(remaining_minutes(&timer, &clock), 25);
+        assert!(timer.pauses(&cycle, &clock).is_empty());
+    }
+
+    #[test]
+    fn abandon_a_break_records_nothing() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25);
+        timer.tick(&cycle, &clock);
+        timer.toggle_pause(&cycle, &clock);
+        clock.advance(10);
+        assert_eq!(timer.abandon(&cycle, clock.now()), None);
+        assert!(timer.waiting());
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+    }
+
+    #[test]
+    fn wait_for_the_user_to_start_a_phase() {
+        let (mut cycle, clock) = (classic(), ManualClock::new());
+        cycle[1].auto_start = false;
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(60);
+        let outcome = timer.tick(&cycle, &clock);
+        assert_eq!(outcome.event, Some(Event::WorkEnd));
+        assert_eq!(outcome.intervals.len(), 1);
+        assert!(timer.waiting() && !timer.paused());
+        assert_eq!(remaining_minutes(&timer, &clock), 5);
+
+        timer.start(&cycle, &clock);
+        assert!(!timer.waiting());
+        clock.advance(5);
+        assert_eq!(timer.tick(&cycle, &clock).event, Some(Event::BreakEnd));
+    }
+
+    #[test]
+    fn unstarted_work_isnt_recorded() {
+        let (mut cycle, clock) = (classic(), ManualClock::new());
+        cycle[2].auto_start = false;
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(30);
+        timer.tick(&cycle, &clock);
+        assert!(timer.waiting());
+        assert_eq!(timer.reset(&cycle, &clock), None);
+
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(30);
+        timer.tick(&cycle, &clock);
+        assert!(timer.skip(&cycle, &clock).intervals.is_empty());
+        assert!(!timer.waiting() && !timer.paused());
+    }
+
+    #[test]
+    fn a_new_cycle_doesnt_change_the_running_break() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25 + 5 + 25 + 5);
+        timer.tick(&cycle, &clock);
+        assert_eq!(timer.kind(&cycle), Kind::LongBreak);
+
+        // The long break is the 5th phase of the old cycle, a working one of
+        // the longer cycle.
+        let mut longer = classic();
+        longer.splice(0..0, classic()[..4].iter().cloned());
+        assert_eq!(timer.kind(&longer), Kind::LongBreak);
+        assert_eq!(timer.index(&longer), 8);
+        assert_eq!(timer.length(&longer), Duration::minutes(15));
+        clock.advance(15);
+        let outcome = timer.tick(&longer, &clock);
+        assert!(outcome.intervals.is_empty());
+        assert_eq!(outcome.event, Some(Event::BreakEnd));
+        assert_eq!(timer.index(&longer), 0);
+        assert_eq!(remaining_minutes(&timer, &clock), 25);
+    }
+
+    #[test]
+    fn a_shorter_cycle_applies_from_the_next_phase() {
+        let (cycle, clock) = (classic(), ManualClock::new());
+        let mut timer = Timer::new(&cycle, &clock);
+        clock.advance(25 + 5 + 25 + 5);
+        timer.tick(&cycle, &clock);
+        assert_eq!(timer.index(&cycle), 4);
+
+        let shorter = vec![step(Kind::Work, 50), step(Kind::Break, 10)];
+        assert_eq!(timer.index(&shorter), 1);
+        assert_eq!(timer.length(&shorter), Duration::minutes(15));
+        clock.advance(15);
+        timer.tick(&shorter, &clock);
+        assert_eq!(timer.index(&shorter), 0);
+        assert_eq!(remaining_minutes(&timer, &clock), 50);
+    }
+}
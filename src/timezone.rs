@@ -0,0 +1,164 @@
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::env;
+
+/// The zone used to display wall clock times.
+///
+/// It's either a fixed offset such as `+08:00`, an IANA zone name such as
+/// `Europe/Berlin` (with its DST rules), or the host's local zone.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub enum Timezone {
+    Local,
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl Default for Timezone {
+    fn default() -> Self {
+        // The plugin runs in a sandbox, honor `TZ` if the host passes it
+        // through, otherwise rely on whatever the runtime considers local.
+        Timezone::from_tz(env::var("TZ").ok())
+    }
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Timezone::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(Timezone::Fixed(FixedOffset::east(0)));
+        }
+        if s.starts_with('+') || s.starts_with('-') {
+            return parse_offset(s)
+                .map(Timezone::Fixed)
+                .ok_or_else(|| format!("invalid UTC offset \"{}\", expect e.g. \"+08:00\"", s));
+        }
+        s.parse::<Tz>()
+            .map(Timezone::Named)
+            .map_err(|_| format!("unknown timezone \"{}\"", s))
+    }
+}
+
+impl Timezone {
+    /// The zone named by the `TZ` variable `tz`, the local one if it's unset
+    /// or invalid.
+    fn from_tz(tz: Option<String>) -> Self {
+        tz.and_then(|tz| Timezone::try_from(tz.trim_start_matches(':').to_string()).ok())
+            .unwrap_or(Timezone::Local)
+    }
+
+    /// Convert `t` into this zone, picking the offset in effect at that time.
+    pub fn localize(&self, t: DateTime<Utc>) -> DateTime<FixedOffset> {
+        let offset = match self {
+            Timezone::Local => Local.offset_from_utc_datetime(&t.naive_utc()).fix(),
+            Timezone::Fixed(offset) => *offset,
+            Timezone::Named(tz) => tz.offset_from_utc_datetime(&t.naive_utc()).fix(),
+        };
+        t.with_timezone(&offset)
+    }
+}
+
+/// Parse `[+-]HH[:MM]` or `[+-]HHMM`.
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let (sign, rest) = s.split_at(1);
+    let (hours, minutes) = match rest.find(':') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    if hours.is_empty()
+        || !hours
+            .chars()
+            .chain(minutes.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    let secs = (hours * 60 + minutes) * 60;
+    FixedOffset::east_opt(if sign == "-" { -secs } else { secs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The offset of `tz` from UTC at `t`, in minutes.
+    fn minutes_east(tz: &Timezone, t: &str) -> i32 {
+        tz.localize(t.parse().unwrap()).offset().local_minus_utc() / 60
+    }
+
+    fn parse(s: &str) -> Result<Timezone, String> {
+        Timezone::try_from(s.to_string())
+    }
+
+    #[test]
+    fn parse_offsets() {
+        for (s, minutes) in &[
+            ("+08:00", 8 * 60),
+            ("+05:30", 5 * 60 + 30),
+            ("+0530", 5 * 60 + 30),
+            ("-8", -8 * 60),
+            ("-03:30", -(3 * 60 + 30)),
+            ("+00:00", 0),
+        ] {
+            let offset = parse_offset(s).unwrap_or_else(|| panic!("{}", s));
+            assert_eq!(offset.local_minus_utc(), minutes * 60, "{}", s);
+        }
+    }
+
+    #[test]
+    fn reject_bad_offsets() {
+        for s in &[
+            "+", "-:30", "+24", "+08:60", "+8h", "+08:", "+8:x", "+123456",
+        ] {
+            assert!(parse_offset(s).is_none(), "{}", s);
+        }
+        assert_eq!(
+            parse("+25:00").err().unwrap(),
+            "invalid UTC offset \"+25:00\", expect e.g. \"+08:00\""
+        );
+    }
+
+    #[test]
+    fn parse_zones() {
+        let summer = "2022-06-21T08:00:00Z";
+        let winter = "2022-12-21T08:00:00Z";
+        let berlin = parse("Europe/Berlin").unwrap();
+        assert_eq!(minutes_east(&berlin, summer), 120);
+        assert_eq!(minutes_east(&berlin, winter), 60);
+        for utc in &["UTC", "utc", "Z", " +00:00 "] {
+            assert_eq!(minutes_east(&parse(utc).unwrap(), summer), 0, "{}", utc);
+        }
+        assert!(matches!(parse("Local"), Ok(Timezone::Local)));
+        assert_eq!(
+            parse("Mars/Olympus").err().unwrap(),
+            "unknown timezone \"Mars/Olympus\""
+        );
+    }
+
+    #[test]
+    fn fall_back_to_the_local_zone() {
+        let t = "2022-06-21T08:00:00Z";
+        let tz = Timezone::from_tz(Some(":Asia/Kolkata".to_string()));
+        assert_eq!(minutes_east(&tz, t), 5 * 60 + 30);
+        let tz = Timezone::from_tz(Some("-02:00".to_string()));
+        assert_eq!(minutes_east(&tz, t), -2 * 60);
+        assert!(matches!(Timezone::from_tz(None), Timezone::Local));
+        assert!(matches!(
+            Timezone::from_tz(Some("Nowhere".to_string())),
+            Timezone::Local
+        ));
+    }
+}